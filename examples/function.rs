@@ -1,55 +1,45 @@
 use std::fs::File;
 use std::io::Write;
 
+use marching_squares::grid::Grid;
+use marching_squares::isobands::isobands;
 use marching_squares::simplify::simplify;
 use marching_squares::svg;
 use marching_squares::{march, Field};
 
+const SIZE: usize = 1600;
+
 #[derive(Debug, Clone)]
 struct Fun {
-    field: Vec<f64>,
+    field: Grid<f64>,
     zrange: (f64, f64),
 }
 
-impl Field for Fun {
-    fn dimensions(&self) -> (usize, usize) {
-        (1600, 1600)
-    }
-
-    fn z_at(&self, x: usize, y: usize) -> f64 {
-        let (_w, h) = self.dimensions();
-        self.field[y * h + x]
-    }
-}
-
 impl Fun {
     fn new() -> Self {
-        let mut fun = Fun {
-            field: Vec::new(),
-            zrange: (std::f64::INFINITY, std::f64::NEG_INFINITY),
-        };
-
-        let (w, h) = fun.dimensions();
-        fun.field.reserve_exact(w * h);
+        let mut zrange = (f64::INFINITY, f64::NEG_INFINITY);
+        let mut samples = Vec::with_capacity(SIZE * SIZE);
 
-        for y in 0..h {
-            for x in 0..w {
-                let z = fun.formula(x, y);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let z = Self::formula(x, y);
 
-                fun.zrange = (fun.zrange.0.min(z), fun.zrange.1.max(z));
-                fun.field.push(z);
+                zrange = (zrange.0.min(z), zrange.1.max(z));
+                samples.push(z);
             }
         }
 
-        fun
+        Fun {
+            field: Grid::new(SIZE, SIZE, samples),
+            zrange,
+        }
     }
 
-    fn formula(&self, x: usize, y: usize) -> f64 {
+    fn formula(x: usize, y: usize) -> f64 {
         let scale = 150.0;
 
-        let (w, h) = self.dimensions();
-        let x = (x as f64 - w as f64 / 2.0) / scale;
-        let y = (y as f64 - h as f64 / 2.0) / scale;
+        let x = (x as f64 - SIZE as f64 / 2.0) / scale;
+        let y = (y as f64 - SIZE as f64 / 2.0) / scale;
 
         (1.3 * x).sin() * (0.9 * y).cos() + (0.8 * x).cos() * (1.9 * y).sin() + (y * 0.2 * x).cos()
     }
@@ -66,18 +56,33 @@ fn main() {
     let mut nofill_doc = svg::Document::new((0.0, 0.0, 1600.0, 1600.0));
     let mut fill_doc = nofill_doc.clone();
 
-    for i in (0..n).into_iter().rev() {
+    let mut lo = f64::NEG_INFINITY;
+    for i in 0..n {
         let t = f64::from(i) / f64::from(n - 1);
         let z = zmin + (zmax - zmin) * t;
 
-        let contours = march(&fun.framed(z), z);
+        let contours = march(&fun.field.framed(z), z);
+
+        nofill_doc = nofill_doc.push(
+            svg::Element::path(contours.into_iter().map(|c| simplify(&c)))
+                .fill("none")
+                .set("stroke", "black")
+                .set("stroke-width", "2"),
+        );
+
+        let rings = isobands(&fun.field, lo, z)
+            .into_iter()
+            .flat_map(|p| std::iter::once(p.exterior).chain(p.holes))
+            .map(|r| simplify(&r));
 
-        let path = svg::Element::path(contours.into_iter().map(|c| simplify(&c)))
-            .set("stroke", "black")
-            .set("stroke-width", "2");
+        fill_doc = fill_doc.push(
+            svg::Element::path(rings)
+                .fill(lerp_colors(c1, c2, t))
+                .set("stroke", "black")
+                .set("stroke-width", "2"),
+        );
 
-        nofill_doc = nofill_doc.push(path.clone().fill("none"));
-        fill_doc = fill_doc.push(path.fill(lerp_colors(c1, c2, t)));
+        lo = z;
     }
 
     for (f, d) in &[
@@ -4,9 +4,10 @@ use std::env;
 use std::io::Write;
 use std::path::Path;
 
+use marching_squares::padding::Padding;
 use marching_squares::simplify::simplify;
 use marching_squares::svg;
-use marching_squares::{march, Field};
+use marching_squares::{march_levels, Field};
 
 #[derive(Debug)]
 struct HeightMap {
@@ -20,14 +21,14 @@ impl HeightMap {
     }
 }
 
-impl Field for HeightMap {
+impl Field<u8> for HeightMap {
     fn dimensions(&self) -> (usize, usize) {
         let (w, h) = self.img.dimensions();
         (w as usize, h as usize)
     }
 
-    fn z_at(&self, x: usize, y: usize) -> f64 {
-        f64::from(self.img.get_pixel(x as u32, y as u32).0[0])
+    fn z_at(&self, x: usize, y: usize) -> u8 {
+        self.img.get_pixel(x as u32, y as u32).0[0]
     }
 }
 
@@ -47,10 +48,20 @@ fn main() {
     let (w, h) = heightmap.dimensions();
     let mut doc = svg::Document::new((0.0, 0.0, w as f64, h as f64));
 
-    for i in 0..nlevels {
-        let t = f64::from(i) / f64::from(nlevels - 1);
-        let z = t * 255.0;
-        let contours = march(&heightmap.framed(z), z)
+    let levels = (0..nlevels)
+        .map(|i| f64::from(i) / f64::from(nlevels - 1) * 255.0)
+        .collect::<Vec<_>>();
+
+    // smooth the quantization noise of the image, which otherwise results in jagged contours
+    let smoothed = heightmap.gaussian_blur(1.0);
+
+    // pad the height map with a value above every level so that all the contours are closed
+    let padded = smoothed.padded(Padding::Value(256.0));
+
+    for (_, contours) in march_levels(&padded, &levels) {
+        let contours = padded
+            .transform()
+            .apply_contours(contours)
             .into_iter()
             .map(|c| simplify(&c));
 
@@ -0,0 +1,263 @@
+//! Find the contours of expensive scalar fields, like implicit functions sampled with `FnField`,
+//! while evaluating only a small portion of their samples.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    add_segment, build_contours, march_cell, Contours, Edge, Field, SaddleResolution, Scalar,
+    SegmentsMap,
+};
+
+/// An `AdaptiveMarcher` finds the contours of a field by starting from a coarse grid of cells and
+/// subdividing, quadtree style, only the cells that might be crossed by a contour.
+///
+/// The cells whose corners straddle the threshold are subdivided down to the cells of the field
+/// and the contours are then followed from those cells, one cell of the field at a time. This
+/// means that the contours are always traced at full resolution even when they cross cells that
+/// weren't subdivided, so that there are no cracks between cells of different sizes and the
+/// contours that are found are exactly the same as the ones found by `march`.
+///
+/// A contour that doesn't cross any straddling cell, like a small island entirely contained in a
+/// coarse cell, is missed unless a bound on the gradient of the field is given with
+/// `with_lipschitz_bound`.
+#[derive(Debug, Clone)]
+pub struct AdaptiveMarcher {
+    coarse_size: usize,
+    lipschitz: Option<f64>,
+}
+
+impl AdaptiveMarcher {
+    /// Create an `AdaptiveMarcher` whose coarsest cells are made of `coarse_size` by
+    /// `coarse_size` cells of the field.
+    ///
+    /// Panics if `coarse_size` is zero.
+    pub fn new(coarse_size: usize) -> Self {
+        assert!(coarse_size > 0, "the coarse cells cannot be empty");
+
+        AdaptiveMarcher {
+            coarse_size,
+            lipschitz: None,
+        }
+    }
+
+    /// Also subdivide the cells that might be crossed by a contour given that the z values of
+    /// the field change by at most `bound` per unit of distance in grid coordinates.
+    pub fn with_lipschitz_bound(mut self, bound: f64) -> Self {
+        self.lipschitz = Some(bound);
+        self
+    }
+
+    /// Find the contours of the given field using `z` as the threshold value.
+    pub fn march<T: Scalar>(&self, field: &impl Field<T>, z: f64) -> Contours {
+        let (width, height) = field.dimensions();
+        if width < 2 || height < 2 {
+            return vec![];
+        }
+
+        let mut samples = Samples {
+            field,
+            cache: HashMap::new(),
+        };
+
+        // find the cells of the field where to start following the contours from.
+        let mut seeds = vec![];
+        for y in (0..height - 1).step_by(self.coarse_size) {
+            for x in (0..width - 1).step_by(self.coarse_size) {
+                let x1 = (x + self.coarse_size).min(width - 1);
+                let y1 = (y + self.coarse_size).min(height - 1);
+                self.subdivide(&mut samples, z, (x, y, x1, y1), &mut seeds);
+            }
+        }
+
+        let mut segments = SegmentsMap::new();
+        let mut visited = seeds.iter().copied().collect::<HashSet<_>>();
+
+        while let Some((x, y)) = seeds.pop() {
+            let zs = samples.cell(x, y);
+
+            let mut edges = vec![];
+            march_cell((x, y), zs, z, SaddleResolution::Fixed, &mut |s, e| {
+                edges.extend([s.0, e.0]);
+                add_segment(&mut segments, s, e);
+            });
+
+            // the contours continue in the cells on the other side of the crossed edges.
+            for edge in edges {
+                let neighbor = match edge {
+                    Edge::Horizontal(ex, ey) if ey == y => ey.checked_sub(1).map(|ny| (ex, ny)),
+                    Edge::Horizontal(ex, ey) => Some((ex, ey)).filter(|_| ey < height - 1),
+                    Edge::Vertical(ex, ey) if ex == x => ex.checked_sub(1).map(|nx| (nx, ey)),
+                    Edge::Vertical(ex, ey) => Some((ex, ey)).filter(|_| ex < width - 1),
+                };
+
+                if let Some(n) = neighbor {
+                    if visited.insert(n) {
+                        seeds.push(n);
+                    }
+                }
+            }
+        }
+
+        build_contours(segments)
+    }
+
+    /// Recursively subdivide the cell going from `(x0, y0)` to `(x1, y1)` if it might be crossed
+    /// by a contour and add the cells of the field it's made of to `seeds`.
+    fn subdivide<T: Scalar>(
+        &self,
+        samples: &mut Samples<impl Field<T>>,
+        z: f64,
+        (x0, y0, x1, y1): (usize, usize, usize, usize),
+        seeds: &mut Vec<(usize, usize)>,
+    ) {
+        let zs = [
+            samples.get(x0, y0),
+            samples.get(x1, y0),
+            samples.get(x1, y1),
+            samples.get(x0, y1),
+        ];
+
+        // cells touching invalid samples are subdivided too, so that the contours around them are
+        // the same as the ones found by `march`.
+        let straddles =
+            zs.iter().any(|v| v.is_nan()) || zs.iter().any(|&v| v > z) != zs.iter().all(|&v| v > z);
+        let might_cross = self.lipschitz.is_some_and(|l| {
+            let distance = zs
+                .iter()
+                .map(|v| (v - z).abs())
+                .fold(f64::INFINITY, f64::min);
+            let half_diagonal = ((x1 - x0) as f64).hypot((y1 - y0) as f64) / 2.0;
+            distance <= l * half_diagonal
+        });
+
+        if !straddles && !might_cross {
+            return;
+        }
+
+        if x1 - x0 == 1 && y1 - y0 == 1 {
+            seeds.push((x0, y0));
+            return;
+        }
+
+        let xs = split(x0, x1);
+        let ys = split(y0, y1);
+        for &(cy0, cy1) in &ys {
+            for &(cx0, cx1) in &xs {
+                self.subdivide(samples, z, (cx0, cy0, cx1, cy1), seeds);
+            }
+        }
+    }
+}
+
+/// Split the given range in two halves, unless it's already as small as possible.
+fn split(start: usize, end: usize) -> Vec<(usize, usize)> {
+    if end - start > 1 {
+        let mid = (start + end) / 2;
+        vec![(start, mid), (mid, end)]
+    } else {
+        vec![(start, end)]
+    }
+}
+
+/// The samples of a field that have been evaluated so far.
+struct Samples<'f, F> {
+    field: &'f F,
+    cache: HashMap<(usize, usize), f64>,
+}
+
+impl<F> Samples<'_, F> {
+    fn get<T: Scalar>(&mut self, x: usize, y: usize) -> f64
+    where
+        F: Field<T>,
+    {
+        let field = self.field;
+
+        // invalid samples are marked as NaN just like `march` does.
+        *self.cache.entry((x, y)).or_insert_with(|| {
+            if field.is_valid(x, y) {
+                field.z_at(x, y).to_f64()
+            } else {
+                f64::NAN
+            }
+        })
+    }
+
+    fn cell<T: Scalar>(&mut self, x: usize, y: usize) -> [f64; 4]
+    where
+        F: Field<T>,
+    {
+        [
+            self.get(x, y),
+            self.get(x + 1, y),
+            self.get(x + 1, y + 1),
+            self.get(x, y + 1),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::function::FnField;
+    use crate::march;
+
+    /// A field that counts how many times it has been sampled.
+    struct Counted<F> {
+        field: F,
+        count: Cell<usize>,
+    }
+
+    impl<F: Field> Field for Counted<F> {
+        fn dimensions(&self) -> (usize, usize) {
+            self.field.dimensions()
+        }
+
+        fn z_at(&self, x: usize, y: usize) -> f64 {
+            self.count.set(self.count.get() + 1);
+            self.field.z_at(x, y)
+        }
+    }
+
+    #[test]
+    fn test_adaptive_same_as_march() {
+        let blobs = |x: f64, y: f64| {
+            (-(x - 1.0).powi(2) - y.powi(2)).exp() + (-(x + 1.2).powi(2) - (y - 0.5).powi(2)).exp()
+        };
+        let field = Counted {
+            field: FnField::new(blobs, (-3.0, -3.0), (6.0, 6.0), (301, 301)),
+            count: Cell::new(0),
+        };
+
+        for z in [0.2, 0.5, 0.9] {
+            field.count.set(0);
+            let contours = AdaptiveMarcher::new(32).march(&field, z);
+            assert!(!contours.is_empty());
+            assert!(field.count.get() < 301 * 301 / 4);
+
+            assert_eq!(contours, march(&field.field, z));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_lipschitz_bound() {
+        // a tiny island that lies entirely inside a coarse cell
+        let island = FnField::new(
+            |x: f64, y: f64| 1.0 - x.hypot(y),
+            (-10.0, -10.0),
+            (20.0, 20.0),
+            (81, 81),
+        );
+
+        assert!(AdaptiveMarcher::new(16).march(&island, 0.5).is_empty());
+
+        // the distance between two samples is 0.25, thus the z values change by at most 0.25
+        // per unit of distance in grid coordinates.
+        let contours = AdaptiveMarcher::new(16)
+            .with_lipschitz_bound(0.25)
+            .march(&island, 0.5);
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours, march(&island, 0.5));
+    }
+}
@@ -0,0 +1,146 @@
+//! Lazy adapters to transform and combine scalar fields, see `Field::map` and the other
+//! combinators of `Field`.
+
+use std::marker::PhantomData;
+
+use crate::{Field, Scalar};
+
+/// A field whose values are the ones of another field passed through a function, see
+/// `Field::map`.
+#[derive(Debug, Clone)]
+pub struct Map<'f, F, M, T = f64> {
+    pub(crate) field: &'f F,
+    pub(crate) f: M,
+    pub(crate) _value: PhantomData<T>,
+}
+
+impl<T: Scalar, F: Field<T>, M: Fn(f64) -> f64> Field for Map<'_, F, M, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        (self.f)(self.field.z_at(x, y).to_f64())
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        self.field.is_valid(x, y)
+    }
+}
+
+/// How the values of the two fields of a `Combine` are combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Op {
+    Add,
+    Mul,
+    Min,
+    Max,
+    Lerp(f64),
+}
+
+/// A field whose values are the combination of the values of two fields with the same
+/// dimensions, see `Field::add`, `Field::mul`, `Field::min`, `Field::max` and `Field::lerp`.
+#[derive(Debug, Clone)]
+pub struct Combine<'f, A, B, T = f64, U = f64> {
+    a: &'f A,
+    b: &'f B,
+    op: Op,
+    _values: PhantomData<(T, U)>,
+}
+
+impl<'f, T: Scalar, U: Scalar, A: Field<T>, B: Field<U>> Combine<'f, A, B, T, U> {
+    /// Panics if the fields have different dimensions.
+    pub(crate) fn new(a: &'f A, b: &'f B, op: Op) -> Self {
+        assert_eq!(
+            a.dimensions(),
+            b.dimensions(),
+            "cannot combine fields with different dimensions"
+        );
+
+        Combine {
+            a,
+            b,
+            op,
+            _values: PhantomData,
+        }
+    }
+}
+
+impl<T: Scalar, U: Scalar, A: Field<T>, B: Field<U>> Field for Combine<'_, A, B, T, U> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.a.dimensions()
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        let a = self.a.z_at(x, y).to_f64();
+        let b = self.b.z_at(x, y).to_f64();
+
+        match self.op {
+            Op::Add => a + b,
+            Op::Mul => a * b,
+            Op::Min => a.min(b),
+            Op::Max => a.max(b),
+            Op::Lerp(t) => a + (b - a) * t,
+        }
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        self.a.is_valid(x, y) && self.b.is_valid(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+    use crate::Field;
+
+    #[test]
+    fn test_combinators() {
+        let a = Grid::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Grid::new(2, 2, vec![4u8, 3, 2, 1]);
+
+        let values = |f: &dyn Field| [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| f.z_at(x, y));
+
+        assert_eq!(values(&a.map(|z| z * 10.0)), [10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(values(&a.add(&b)), [5.0, 5.0, 5.0, 5.0]);
+        assert_eq!(values(&a.mul(&b)), [4.0, 6.0, 6.0, 4.0]);
+        assert_eq!(values(&a.min(&b)), [1.0, 2.0, 2.0, 1.0]);
+        assert_eq!(values(&a.max(&b)), [4.0, 3.0, 3.0, 4.0]);
+        assert_eq!(values(&a.lerp(&b, 0.25)), [1.75, 2.25, 2.75, 3.25]);
+
+        // adapters can be chained
+        let c = a.add(&b);
+        assert_eq!(values(&c.map(|z| -z)), [-5.0; 4]);
+    }
+
+    #[test]
+    fn test_combinators_validity() {
+        struct Holey;
+        impl Field for Holey {
+            fn dimensions(&self) -> (usize, usize) {
+                (2, 2)
+            }
+
+            fn z_at(&self, _x: usize, _y: usize) -> f64 {
+                0.0
+            }
+
+            fn is_valid(&self, x: usize, y: usize) -> bool {
+                (x, y) != (1, 1)
+            }
+        }
+
+        let a = Grid::new(2, 2, vec![1.0; 4]);
+        assert!(!a.add(&Holey).is_valid(1, 1));
+        assert!(a.add(&Holey).is_valid(0, 1));
+        assert!(!Holey.map(|z| z + 1.0).is_valid(1, 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_combine_different_dimensions() {
+        let a = Grid::new(2, 2, vec![1.0; 4]);
+        let b = Grid::new(3, 2, vec![1.0; 6]);
+        a.add(&b);
+    }
+}
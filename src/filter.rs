@@ -0,0 +1,241 @@
+//! Smoothing filters to suppress the noise of scalar fields before finding their contours, see
+//! `Field::box_blur`, `Field::gaussian_blur` and `Field::median_filter`.
+
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use crate::{Field, Scalar};
+
+/// The filter applied by a `Filtered` field.
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// A separable convolution with the given symmetric 1D kernel, whose length is odd.
+    Convolution(Vec<f64>),
+
+    /// The median of the square window with the given radius.
+    Median(usize),
+}
+
+/// A field whose values are the ones of another field smoothed with a filter.
+///
+/// The invalid samples of the original field, see `Field::is_valid`, are ignored by the filters
+/// and so are the samples outside of it, that is the filters only use the valid samples of the
+/// window around each sample.
+#[derive(Debug)]
+pub struct Filtered<'f, F, T = f64> {
+    field: &'f F,
+    filter: Filter,
+
+    /// The result of the horizontal pass of the convolution, calculated the first time a value
+    /// is requested.
+    horizontal: OnceLock<Vec<f64>>,
+
+    _value: PhantomData<T>,
+}
+
+impl<'f, T: Scalar, F: Field<T>> Filtered<'f, F, T> {
+    /// Panics if `radius` is zero.
+    pub(crate) fn box_blur(field: &'f F, radius: usize) -> Self {
+        assert!(radius > 0, "the radius of the filter cannot be zero");
+        Self::new(field, Filter::Convolution(vec![1.0; 2 * radius + 1]))
+    }
+
+    /// Panics if `sigma` is not positive.
+    pub(crate) fn gaussian_blur(field: &'f F, sigma: f64) -> Self {
+        assert!(
+            sigma > 0.0,
+            "the standard deviation of the filter must be positive"
+        );
+
+        // the weights beyond three standard deviations are negligible
+        let radius = (3.0 * sigma).ceil() as isize;
+        let kernel = (-radius..=radius)
+            .map(|i| (-((i * i) as f64) / (2.0 * sigma * sigma)).exp())
+            .collect();
+
+        Self::new(field, Filter::Convolution(kernel))
+    }
+
+    /// Panics if `radius` is zero.
+    pub(crate) fn median(field: &'f F, radius: usize) -> Self {
+        assert!(radius > 0, "the radius of the filter cannot be zero");
+        Self::new(field, Filter::Median(radius))
+    }
+
+    fn new(field: &'f F, filter: Filter) -> Self {
+        Filtered {
+            field,
+            filter,
+            horizontal: OnceLock::new(),
+            _value: PhantomData,
+        }
+    }
+
+    /// The value of the original field at the given position, NaN if the sample is invalid.
+    fn original(&self, x: usize, y: usize) -> f64 {
+        if self.field.is_valid(x, y) {
+            self.field.z_at(x, y).to_f64()
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+impl<T: Scalar, F: Field<T>> Field for Filtered<'_, F, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.field.dimensions()
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        let (w, h) = self.dimensions();
+
+        match &self.filter {
+            Filter::Convolution(kernel) => {
+                let horizontal = self.horizontal.get_or_init(|| {
+                    (0..h)
+                        .flat_map(|y| {
+                            let row = (0..w).map(|x| self.original(x, y)).collect::<Vec<_>>();
+                            (0..w).map(move |x| convolve(kernel, x, |i| row.get(i).copied()))
+                        })
+                        .collect()
+                });
+
+                convolve(kernel, y, |i| (i < h).then(|| horizontal[i * w + x]))
+            }
+            Filter::Median(radius) => {
+                let mut window = (y.saturating_sub(*radius)..(y + radius + 1).min(h))
+                    .flat_map(|wy| {
+                        (x.saturating_sub(*radius)..(x + radius + 1).min(w)).map(move |wx| (wx, wy))
+                    })
+                    .map(|(wx, wy)| self.original(wx, wy))
+                    .filter(|z| !z.is_nan())
+                    .collect::<Vec<_>>();
+
+                if window.is_empty() {
+                    return f64::NAN;
+                }
+
+                window.sort_by(f64::total_cmp);
+                let mid = window.len() / 2;
+                if window.len() % 2 == 0 {
+                    (window[mid - 1] + window[mid]) / 2.0
+                } else {
+                    window[mid]
+                }
+            }
+        }
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        self.field.is_valid(x, y)
+    }
+}
+
+/// Convolve the values around position `i` with the given kernel, `get` returns the value at a
+/// given position or `None` if the position is outside the field. The weights of the missing and
+/// NaN values are ignored.
+fn convolve(kernel: &[f64], i: usize, get: impl Fn(usize) -> Option<f64>) -> f64 {
+    let radius = kernel.len() / 2;
+
+    let (sum, weights) = kernel
+        .iter()
+        .enumerate()
+        .filter_map(|(k, w)| {
+            let j = (i + k).checked_sub(radius)?;
+            get(j).filter(|z| !z.is_nan()).map(|z| (z * w, *w))
+        })
+        .fold((0.0, 0.0), |(s, ws), (z, w)| (s + z, ws + w));
+
+    if weights == 0.0 {
+        f64::NAN
+    } else {
+        sum / weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+    use crate::Field;
+
+    fn impulse() -> Grid<f64> {
+        let mut samples = vec![0.0; 25];
+        samples[12] = 9.0;
+        Grid::new(5, 5, samples)
+    }
+
+    #[test]
+    fn test_box_blur() {
+        let grid = impulse();
+        let blurred = grid.box_blur(1);
+
+        assert_eq!(blurred.z_at(2, 2), 1.0);
+        assert_eq!(blurred.z_at(1, 3), 1.0);
+        assert_eq!(blurred.z_at(0, 0), 0.0);
+
+        // the samples outside the field are ignored
+        let grid = Grid::new(3, 3, vec![0.0, 0.0, 0.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(grid.box_blur(1).z_at(0, 0), 2.0);
+
+        let constant = Grid::new(4, 3, vec![3.0; 12]);
+        assert_eq!(constant.box_blur(2).z_at(3, 1), 3.0);
+    }
+
+    #[test]
+    fn test_gaussian_blur() {
+        let grid = impulse();
+        let blurred = grid.gaussian_blur(0.8);
+
+        let center = blurred.z_at(2, 2);
+        assert!(center < 9.0 && center > 0.0);
+        assert!(blurred.z_at(1, 2) < center);
+        assert_eq!(blurred.z_at(1, 2), blurred.z_at(3, 2));
+        assert_eq!(blurred.z_at(2, 1), blurred.z_at(1, 2));
+
+        let total = (0..5)
+            .flat_map(|y| (0..5).map(move |x| (x, y)))
+            .map(|(x, y)| blurred.z_at(x, y))
+            .sum::<f64>();
+        assert!(total > 8.0 && total < 9.5);
+    }
+
+    #[test]
+    fn test_median_filter() {
+        let grid = impulse();
+        let filtered = grid.median_filter(1);
+
+        // the spike is removed completely
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(filtered.z_at(x, y), 0.0);
+            }
+        }
+
+        let grid = Grid::new(3, 1, vec![1.0, 5.0, 2.0]);
+        assert_eq!(grid.median_filter(1).z_at(1, 0), 2.0);
+        assert_eq!(grid.median_filter(1).z_at(0, 0), 3.0);
+    }
+
+    #[test]
+    fn test_filters_skip_invalid_samples() {
+        struct Holey;
+        impl Field for Holey {
+            fn dimensions(&self) -> (usize, usize) {
+                (3, 1)
+            }
+
+            fn z_at(&self, x: usize, _y: usize) -> f64 {
+                [1.0, 100.0, 3.0][x]
+            }
+
+            fn is_valid(&self, x: usize, _y: usize) -> bool {
+                x != 1
+            }
+        }
+
+        assert_eq!(Holey.box_blur(1).z_at(0, 0), 1.0);
+        assert_eq!(Holey.box_blur(1).z_at(1, 0), 2.0);
+        assert!(!Holey.box_blur(1).is_valid(1, 0));
+        assert_eq!(Holey.median_filter(1).z_at(1, 0), 2.0);
+    }
+}
@@ -0,0 +1,86 @@
+//! Scalar fields defined by a continuous function, like the analytic ones used for plotting.
+
+use crate::transform::Transform;
+use crate::Field;
+
+/// A scalar field that samples the function `f(x, y)` on a regular grid covering a rectangle of
+/// the plane.
+///
+/// The contours found on this field are in grid coordinates, like for any other field, and they
+/// can be mapped back to the coordinates of the function with `transform`.
+#[derive(Debug, Clone)]
+pub struct FnField<F> {
+    f: F,
+    origin: (f64, f64),
+    size: (f64, f64),
+    resolution: (usize, usize),
+}
+
+impl<F: Fn(f64, f64) -> f64> FnField<F> {
+    /// Create a field that samples `f` on a grid of `resolution` samples covering the rectangle
+    /// with the given origin, its top left corner, and size. The samples on the boundary of the
+    /// grid lie on the boundary of the rectangle.
+    pub fn new(f: F, origin: (f64, f64), size: (f64, f64), resolution: (usize, usize)) -> Self {
+        FnField {
+            f,
+            origin,
+            size,
+            resolution,
+        }
+    }
+
+    /// The transform mapping the grid coordinates to the coordinates of the function.
+    pub fn transform(&self) -> Transform {
+        let (w, h) = self.resolution;
+        let pixel_size = (
+            self.size.0 / w.saturating_sub(1).max(1) as f64,
+            self.size.1 / h.saturating_sub(1).max(1) as f64,
+        );
+
+        Transform::new(self.origin, pixel_size)
+    }
+}
+
+impl<F: Fn(f64, f64) -> f64> Field for FnField<F> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.resolution
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        self.sample(x as f64, y as f64)
+    }
+
+    /// Evaluate the function at the given position, without interpolating between the samples.
+    fn sample(&self, x: f64, y: f64) -> f64 {
+        let (x, y) = self.transform().apply((x, y));
+        (self.f)(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::march;
+
+    #[test]
+    fn test_fn_field() {
+        let circle = FnField::new(
+            |x: f64, y: f64| 1.0 - x.hypot(y),
+            (-2.0, -2.0),
+            (4.0, 4.0),
+            (41, 41),
+        );
+
+        assert_eq!(circle.dimensions(), (41, 41));
+        assert_eq!(circle.z_at(20, 20), 1.0);
+        assert_eq!(circle.transform().apply((0.0, 0.0)), (-2.0, -2.0));
+        assert_eq!(circle.transform().apply((40.0, 40.0)), (2.0, 2.0));
+
+        let contours = circle.transform().apply_contours(march(&circle, 0.0));
+        assert_eq!(contours.len(), 1);
+        assert_eq!(contours[0][0], contours[0][contours[0].len() - 1]);
+        for &(x, y) in &contours[0] {
+            assert!((x.hypot(y) - 1.0).abs() < 1e-2);
+        }
+    }
+}
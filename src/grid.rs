@@ -0,0 +1,260 @@
+//! Scalar fields backed by a buffer of samples.
+
+use crate::{Field, Scalar};
+
+/// How the samples of a grid are laid out in its buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// The samples of each row are contiguous and the rows follow each other.
+    #[default]
+    RowMajor,
+
+    /// The samples of each column are contiguous and the columns follow each other.
+    ColumnMajor,
+}
+
+/// A scalar field whose samples are stored in a `Vec`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    shape: Shape,
+}
+
+/// A scalar field whose samples are stored in a borrowed slice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridRef<'a, T> {
+    data: &'a [T],
+    shape: Shape,
+}
+
+/// The dimensions of a grid and how they map to indices of its buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Shape {
+    width: usize,
+    height: usize,
+    layout: Layout,
+
+    /// The distance between the first samples of two consecutive rows, or columns if the layout
+    /// is column major.
+    stride: usize,
+}
+
+impl Shape {
+    fn new(width: usize, height: usize, layout: Layout) -> Self {
+        let stride = match layout {
+            Layout::RowMajor => width,
+            Layout::ColumnMajor => height,
+        };
+
+        Shape {
+            width,
+            height,
+            layout,
+            stride,
+        }
+    }
+
+    fn with_stride(self, stride: usize) -> Self {
+        let line = match self.layout {
+            Layout::RowMajor => self.width,
+            Layout::ColumnMajor => self.height,
+        };
+        assert!(stride >= line, "stride is smaller than a line of the grid");
+
+        Shape { stride, ..self }
+    }
+
+    /// The minimum length of a buffer holding all the samples.
+    fn len(&self) -> usize {
+        if self.width == 0 || self.height == 0 {
+            return 0;
+        }
+
+        self.index(self.width - 1, self.height - 1) + 1
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        match self.layout {
+            Layout::RowMajor => y * self.stride + x,
+            Layout::ColumnMajor => x * self.stride + y,
+        }
+    }
+
+    fn check(self, len: usize) -> Self {
+        assert!(
+            len >= self.len(),
+            "the buffer has {} samples, but the grid needs {}",
+            len,
+            self.len()
+        );
+        self
+    }
+}
+
+impl<T> Grid<T> {
+    /// Create a grid of `width` by `height` samples stored in row major order.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn new(width: usize, height: usize, data: Vec<T>) -> Self {
+        Self::with_layout(width, height, Layout::RowMajor, data)
+    }
+
+    /// Create a grid of `width` by `height` samples stored in column major order.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn column_major(width: usize, height: usize, data: Vec<T>) -> Self {
+        Self::with_layout(width, height, Layout::ColumnMajor, data)
+    }
+
+    /// Create a grid of `width` by `height` samples stored with the given layout.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn with_layout(width: usize, height: usize, layout: Layout, data: Vec<T>) -> Self {
+        let shape = Shape::new(width, height, layout).check(data.len());
+        Grid { data, shape }
+    }
+
+    /// Set the number of samples between the starts of two consecutive rows, or columns if the
+    /// layout is column major, to skip the padding at the end of each of them.
+    ///
+    /// Panics if the stride is smaller than a row, or a column, or if the buffer doesn't have
+    /// enough samples.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.shape = self.shape.with_stride(stride).check(self.data.len());
+        self
+    }
+
+    /// Borrow the grid as a `GridRef`.
+    pub fn as_grid_ref(&self) -> GridRef<'_, T> {
+        GridRef {
+            data: &self.data,
+            shape: self.shape,
+        }
+    }
+
+    /// Get the buffer of samples back.
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<'a, T> GridRef<'a, T> {
+    /// Create a grid of `width` by `height` samples stored in row major order.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn new(width: usize, height: usize, data: &'a [T]) -> Self {
+        Self::with_layout(width, height, Layout::RowMajor, data)
+    }
+
+    /// Create a grid of `width` by `height` samples stored in column major order.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn column_major(width: usize, height: usize, data: &'a [T]) -> Self {
+        Self::with_layout(width, height, Layout::ColumnMajor, data)
+    }
+
+    /// Create a grid of `width` by `height` samples stored with the given layout.
+    ///
+    /// Panics if `data` doesn't have enough samples.
+    pub fn with_layout(width: usize, height: usize, layout: Layout, data: &'a [T]) -> Self {
+        let shape = Shape::new(width, height, layout).check(data.len());
+        GridRef { data, shape }
+    }
+
+    /// See `Grid::with_stride`.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.shape = self.shape.with_stride(stride).check(self.data.len());
+        self
+    }
+}
+
+impl<T: Scalar> Field<T> for Grid<T> {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.shape.width, self.shape.height)
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> T {
+        self.data[self.shape.index(x, y)]
+    }
+}
+
+impl<T: Scalar> Field<T> for GridRef<'_, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.shape.width, self.shape.height)
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> T {
+        self.data[self.shape.index(x, y)]
+    }
+}
+
+impl<T> From<(usize, usize, Vec<T>)> for Grid<T> {
+    /// Create a row major grid from its width, height and samples.
+    fn from((width, height, data): (usize, usize, Vec<T>)) -> Self {
+        Grid::new(width, height, data)
+    }
+}
+
+impl<'a, T> From<(usize, usize, &'a [T])> for GridRef<'a, T> {
+    /// Create a row major grid from its width, height and samples.
+    fn from((width, height, data): (usize, usize, &'a [T])) -> Self {
+        GridRef::new(width, height, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::march;
+
+    #[test]
+    fn test_grid_layouts() {
+        #[rustfmt::skip]
+        let rows = vec![
+            0, 0, 0, 0,
+            0, 1, 1, 0,
+            0, 0, 0, 0u8,
+        ];
+        #[rustfmt::skip]
+        let cols = vec![
+            0, 0, 0,
+            0, 1, 0,
+            0, 1, 0,
+            0, 0, 0u8,
+        ];
+        #[rustfmt::skip]
+        let padded = vec![
+            0, 0, 0, 0, 9,
+            0, 1, 1, 0, 9,
+            0, 0, 0, 0u8,
+        ];
+
+        let grid = Grid::from((4, 3, rows.clone()));
+        assert_eq!(grid.dimensions(), (4, 3));
+        assert_eq!(grid.z_at(2, 1), 1);
+        assert_eq!(grid.z_at(3, 1), 0);
+
+        let expected = march(&grid, 0.5);
+        assert_eq!(expected.len(), 1);
+
+        assert_eq!(march(&grid.as_grid_ref(), 0.5), expected);
+        assert_eq!(march(&GridRef::from((4, 3, &rows[..])), 0.5), expected);
+        assert_eq!(march(&Grid::column_major(4, 3, cols), 0.5), expected);
+        assert_eq!(
+            march(&GridRef::new(4, 3, &padded).with_stride(5), 0.5),
+            expected
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_grid_too_small() {
+        Grid::new(4, 3, vec![0.0; 11]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_grid_stride_too_big() {
+        Grid::new(4, 3, vec![0.0; 12]).with_stride(5);
+    }
+}
@@ -0,0 +1,323 @@
+//! Filled contours, aka isobands, of a scalar field.
+
+use std::collections::HashMap;
+
+use crate::{
+    add_segment, build_contours, cell_crossings, for_each_cell, is_invalid_cell, march_cell,
+    nesting, Crossing, Edge, Field, SaddleResolution, Scalar, SegmentsMap,
+};
+
+pub use crate::nesting::Polygon;
+
+/// The points the boundaries of the band are made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    /// The point where the `lo` isoline crosses the given edge.
+    Lo(Edge),
+
+    /// The point where the `hi` isoline crosses the given edge.
+    Hi(Edge),
+
+    /// A sample of the field at the given position, only used on the boundary of the field.
+    Corner(usize, usize),
+
+    /// A sample on the boundary of two valid cells that only touch diagonally, because the other
+    /// two cells around it are invalid. The flag tells whether the boundary is the one of the cell
+    /// on the left of the sample, so that the boundaries of the two cells are kept separate.
+    Pinch(usize, usize, bool),
+}
+
+/// Find the polygons covering the region of the scalar field whose values `v` are such that
+/// `lo < v <= hi`.
+///
+/// Each cell is classified in one of the 81 cases given by the ternary state (below, inside or
+/// above the band) of its four corners. The boundary of the band inside the cell is then made of
+/// the `lo` and `hi` isolines, oriented so that the band is on their left, plus the portions of
+/// the field boundary that fall inside the band so that the polygons are always closed.
+///
+/// The cells touching invalid samples, see `Field::is_valid`, are treated as if they were outside
+/// the field, thus the polygons are closed around them.
+pub fn isobands<T: Scalar>(field: &impl Field<T>, lo: f64, hi: f64) -> Vec<Polygon> {
+    let (width, height) = field.dimensions();
+
+    let mut segments = SegmentsMap::new();
+
+    // the portions of the boundary of the field and of the invalid cells inside the band, together
+    // with the position of the valid cell they belong to.
+    let mut boundary = vec![];
+
+    let state = |z: f64| {
+        if z > hi {
+            2
+        } else if z > lo {
+            1
+        } else {
+            0
+        }
+    };
+
+    // whether the cells of the previous and current row are valid, that is they don't touch any
+    // invalid sample.
+    let mut prev_row_valid = vec![];
+    let mut row_valid = vec![];
+
+    for_each_cell(field, |(x, y), zs| {
+        if x == 0 {
+            std::mem::swap(&mut prev_row_valid, &mut row_valid);
+            row_valid.clear();
+        }
+
+        let valid = !is_invalid_cell(zs);
+        row_valid.push(valid);
+
+        // whether each edge is shared with a valid cell. The edges that are not, either because
+        // they're on the boundary of the field or because they're shared with an invalid cell,
+        // are on the boundary of the band too. Since the cells below and on the right haven't been
+        // visited yet, the edges shared with them are handled when visiting them.
+        let top_shared = y > 0 && prev_row_valid[x];
+        let left_shared = x > 0 && row_valid[x - 1];
+        let right_shared = x + 2 < width;
+        let bottom_shared = y + 2 < height;
+
+        let states = zs.map(state);
+        let corner = |x: usize, y: usize| (Node::Corner(x, y), (x as f64, y as f64));
+        let ul = (corner(x, y), states[0]);
+        let ur = (corner(x + 1, y), states[1]);
+        let br = (corner(x + 1, y + 1), states[2]);
+        let bl = (corner(x, y + 1), states[3]);
+
+        if !valid {
+            // walk the edges of the valid neighbors keeping the band on the left.
+            if top_shared || left_shared {
+                let [lo_top, _, _, lo_left] = cell_crossings((x, y), zs, lo);
+                let [hi_top, _, _, hi_left] = cell_crossings((x, y), zs, hi);
+
+                if top_shared {
+                    add_boundary_seg(ul, ur, (lo_top, hi_top), &mut |s, e| {
+                        boundary.push(((x, y - 1), s, e))
+                    });
+                }
+                if left_shared {
+                    add_boundary_seg(bl, ul, (lo_left, hi_left), &mut |s, e| {
+                        boundary.push(((x - 1, y), s, e))
+                    });
+                }
+            }
+
+            return;
+        }
+
+        let case = states[3] + 3 * states[2] + 9 * states[1] + 27 * states[0];
+
+        // everything below or above the band, nothing to do
+        if case == 0 || case == 80 {
+            return;
+        }
+
+        // if the cell lies entirely inside the band then only its edges on the boundary
+        // contribute to the polygons.
+        if case != 40 {
+            march_cell((x, y), zs, lo, SaddleResolution::Fixed, &mut |s, e| {
+                add_segment(&mut segments, (Node::Lo(s.0), s.1), (Node::Lo(e.0), e.1))
+            });
+            march_cell((x, y), zs, hi, SaddleResolution::Fixed, &mut |s, e| {
+                add_segment(&mut segments, (Node::Hi(e.0), e.1), (Node::Hi(s.0), s.1))
+            });
+        }
+
+        if top_shared && left_shared && right_shared && bottom_shared {
+            return;
+        }
+
+        let [lo_top, lo_right, lo_bottom, lo_left] = cell_crossings((x, y), zs, lo);
+        let [hi_top, hi_right, hi_bottom, hi_left] = cell_crossings((x, y), zs, hi);
+
+        // walk the boundary keeping the band on the left.
+        let mut add_seg = |s, e| boundary.push(((x, y), s, e));
+        if !top_shared {
+            add_boundary_seg(ur, ul, (lo_top, hi_top), &mut add_seg);
+        }
+        if !right_shared {
+            add_boundary_seg(br, ur, (lo_right, hi_right), &mut add_seg);
+        }
+        if !bottom_shared {
+            add_boundary_seg(bl, br, (lo_bottom, hi_bottom), &mut add_seg);
+        }
+        if !left_shared {
+            add_boundary_seg(ul, bl, (lo_left, hi_left), &mut add_seg);
+        }
+    });
+
+    // a sample where two valid cells touch diagonally is the start of the boundaries of both, that
+    // are then joined separately keying the sample by the cell they belong to.
+    let mut starts = HashMap::new();
+    for (_, (k, _), _) in &boundary {
+        *starts.entry(*k).or_insert(0) += 1;
+    }
+
+    for ((cx, _), s, e) in boundary {
+        let key = |(k, p): Crossing<Node>| match k {
+            Node::Corner(x, y) if starts.get(&k).is_some_and(|&n| n > 1) => {
+                (Node::Pinch(x, y, cx < x), p)
+            }
+            _ => (k, p),
+        };
+
+        add_segment(&mut segments, key(s), key(e));
+    }
+
+    nesting::polygons(&build_contours(segments))
+}
+
+/// Add the portion of the boundary edge going from `start` to `end` that lies inside the band.
+/// The crossings are the points where the `lo` and `hi` isolines cross the edge.
+fn add_boundary_seg(
+    (start, start_state): (Crossing<Node>, u8),
+    (end, end_state): (Crossing<Node>, u8),
+    (lo_crossing, hi_crossing): (Crossing, Crossing),
+    add_seg: &mut impl FnMut(Crossing<Node>, Crossing<Node>),
+) {
+    if start_state == end_state && start_state != 1 {
+        return;
+    }
+
+    let endpoint = |p, state| match state {
+        0 => (Node::Lo(lo_crossing.0), lo_crossing.1),
+        1 => p,
+        _ => (Node::Hi(hi_crossing.0), hi_crossing.1),
+    };
+
+    add_seg(endpoint(start, start_state), endpoint(end, end_state));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::nesting::signed_area;
+    use crate::tests::integer_noise;
+
+    struct Pyramid;
+    impl Field for Pyramid {
+        fn dimensions(&self) -> (usize, usize) {
+            (7, 7)
+        }
+
+        fn z_at(&self, x: usize, y: usize) -> f64 {
+            let d = x.abs_diff(3).max(y.abs_diff(3));
+            3.0 - d as f64
+        }
+    }
+
+    #[test]
+    fn test_isobands_empty() {
+        assert!(isobands(&Pyramid, 10.0, 20.0).is_empty());
+        assert!(isobands(&Pyramid, -20.0, -10.0).is_empty());
+    }
+
+    #[test]
+    fn test_isobands_whole_field() {
+        let polys = isobands(&Pyramid, -1.0, 10.0);
+
+        assert_eq!(polys.len(), 1);
+        assert!(polys[0].holes.is_empty());
+        assert_eq!(signed_area(&polys[0].exterior), -36.0);
+    }
+
+    #[test]
+    fn test_isobands_ring() {
+        let polys = isobands(&Pyramid, 0.5, 2.5);
+
+        assert_eq!(polys.len(), 1);
+        assert_eq!(polys[0].holes.len(), 1);
+        assert_eq!(signed_area(&polys[0].exterior), -24.5);
+        assert_eq!(signed_area(&polys[0].holes[0]), 0.5);
+    }
+
+    #[test]
+    fn test_isobands_invalid_samples() {
+        struct Holey;
+        impl Field for Holey {
+            fn dimensions(&self) -> (usize, usize) {
+                Pyramid.dimensions()
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                Pyramid.z_at(x, y)
+            }
+
+            fn is_valid(&self, x: usize, y: usize) -> bool {
+                (x, y) != (3, 3) && (x, y) != (0, 6)
+            }
+        }
+
+        let polys = isobands(&Holey, -1.0, 10.0);
+
+        assert_eq!(polys.len(), 1);
+        assert_eq!(polys[0].holes.len(), 1);
+        assert_eq!(signed_area(&polys[0].exterior), -35.0);
+        assert_eq!(signed_area(&polys[0].holes[0]), 4.0);
+    }
+
+    #[test]
+    fn test_isobands_invalid_samples_touching_diagonally() {
+        let nan = f64::NAN;
+        let grid = Grid::new(3, 3, vec![0.0, 0.0, nan, 0.0, 0.0, 0.0, nan, 0.0, 0.0]);
+
+        let polys = isobands(&grid, -1.0, 0.5);
+
+        assert_eq!(polys.len(), 2);
+        for poly in polys {
+            assert!(poly.holes.is_empty());
+            assert_eq!(signed_area(&poly.exterior), -1.0);
+        }
+    }
+
+    #[test]
+    fn test_isobands_partition_valid_cells() {
+        let levels = [-1.0, 0.5, 1.5, 2.5, 10.0];
+
+        for seed in 0..50 {
+            // the samples at 4 are invalid
+            let noise = integer_noise(8, 7, seed);
+            let (w, h) = noise.dimensions();
+            let samples = (0..h)
+                .flat_map(|y| (0..w).map(move |x| (x, y)))
+                .map(|(x, y)| match noise.z_at(x, y) {
+                    4 => f64::NAN,
+                    z => f64::from(z),
+                })
+                .collect::<Vec<_>>();
+            let grid = Grid::new(w, h, samples);
+
+            let valid_cells = (0..h - 1)
+                .flat_map(|y| (0..w - 1).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                        .iter()
+                        .all(|&(x, y)| !grid.z_at(x, y).is_nan())
+                })
+                .count();
+
+            let area = levels
+                .windows(2)
+                .flat_map(|band| isobands(&grid, band[0], band[1]))
+                .map(|p| {
+                    -signed_area(&p.exterior) - p.holes.iter().map(|h| signed_area(h)).sum::<f64>()
+                })
+                .sum::<f64>();
+
+            assert!((area - valid_cells as f64).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_isobands_touching_boundary() {
+        let polys = isobands(&Pyramid, -1.0, 1.5);
+
+        assert_eq!(polys.len(), 1);
+        assert_eq!(polys[0].holes.len(), 1);
+        assert_eq!(signed_area(&polys[0].exterior), -36.0);
+        assert_eq!(signed_area(&polys[0].holes[0]), 8.5);
+    }
+}
@@ -1,216 +1,872 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Range;
 
+use combinators::{Combine, Map, Op};
+use filter::Filtered;
+use padding::{Padded, Padding};
+use resample::{Interpolation, Resampled};
+use view::View;
+
+pub mod adaptive;
+pub mod combinators;
+pub mod filter;
+pub mod function;
+pub mod grid;
+pub mod isobands;
+pub mod marching_cubes;
+pub mod marching_triangles;
+pub mod nesting;
+pub mod padding;
+pub mod refine;
+pub mod resample;
 pub mod simplify;
+pub mod slice;
+pub mod streaming;
+pub mod tiles;
+pub mod transform;
+pub mod view;
 
 #[cfg(feature = "svg")]
 pub mod svg;
 
-/// A scalar field.
-pub trait Field {
+/// A scalar field whose values are of type `T`, `f64` by default.
+///
+/// The contours are always found by converting the values to `f64`, but storing the samples in a
+/// smaller type, like `u8` for images or `f32` for large rasters, saves memory and bandwidth.
+pub trait Field<T: Scalar = f64> {
     /// Get the width and height of the scalar field.
     fn dimensions(&self) -> (usize, usize);
 
     /// Calculate the z value at the given position. The position is always inside the range of
     /// `dimensions`.
-    fn z_at(&self, x: usize, y: usize) -> f64;
+    fn z_at(&self, x: usize, y: usize) -> T;
+
+    /// Whether the sample at the given position holds valid data. The cells that touch an invalid
+    /// sample, or a sample whose z value is NaN, are skipped and the contours that would cross them
+    /// terminate as open paths on their boundary instead.
+    ///
+    /// By default every sample is valid.
+    fn is_valid(&self, _x: usize, _y: usize) -> bool {
+        true
+    }
+
+    /// Calculate the z value at the given fractional position, which is clamped to the range of
+    /// `dimensions`.
+    ///
+    /// By default the z value is the bilinear interpolation of the four samples around the
+    /// position, but fields that can be evaluated anywhere, like `FnField`, can do better. The z
+    /// value of an empty field is NaN.
+    fn sample(&self, x: f64, y: f64) -> f64 {
+        let (w, h) = self.dimensions();
+        if w == 0 || h == 0 {
+            return f64::NAN;
+        }
+
+        let x = x.clamp(0.0, w.saturating_sub(1) as f64);
+        let y = y.clamp(0.0, h.saturating_sub(1) as f64);
+
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = (
+            (x0 + 1).min(w.saturating_sub(1)),
+            (y0 + 1).min(h.saturating_sub(1)),
+        );
+        let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+
+        // only read the next sample when it contributes to the result, so that positions on the
+        // edges between valid samples aren't affected by NaN samples next to them.
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let z = |x, y| self.z_at(x, y).to_f64();
+        let row = |y| {
+            if tx > 0.0 {
+                lerp(z(x0, y), z(x1, y), tx)
+            } else {
+                z(x0, y)
+            }
+        };
+
+        if ty > 0.0 {
+            lerp(row(y0), row(y1), ty)
+        } else {
+            row(y0)
+        }
+    }
 
     /// Helper to force a Field to have all the Z values at the boundaries of the field to be set
     /// to `border_z`. Useful to ensure each path is closed.
-    fn framed(&self, border_z: f64) -> Framed<Self>
+    ///
+    /// Note that the samples on the boundary of this field are lost, use `padded` to keep them.
+    fn framed(&self, border_z: f64) -> Framed<'_, Self, T>
     where
         Self: Sized,
     {
         Framed {
             field: self,
             border_z,
+            _value: PhantomData,
+        }
+    }
+
+    /// Create a field that surrounds this field with a ring of samples filled according to
+    /// `padding`, useful to close the contours that would otherwise end on the boundary without
+    /// losing the samples on it like `framed` does.
+    ///
+    /// The contours found on the padded field can be mapped back to this field with
+    /// `Padded::transform`.
+    fn padded(&self, padding: Padding) -> Padded<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Padded::new(self, padding)
+    }
+
+    /// Create a field whose values are the ones of this field passed through `f`, for example to
+    /// offset or scale them.
+    fn map<M: Fn(f64) -> f64>(&self, f: M) -> Map<'_, Self, M, T>
+    where
+        Self: Sized,
+    {
+        Map {
+            field: self,
+            f,
+            _value: PhantomData,
         }
     }
+
+    /// Create a field whose values are the sum of the values of this field and of `other`.
+    ///
+    /// Panics if the fields have different dimensions, just like all the other combinators of two
+    /// fields.
+    fn add<'f, U: Scalar, O: Field<U>>(&'f self, other: &'f O) -> Combine<'f, Self, O, T, U>
+    where
+        Self: Sized,
+    {
+        Combine::new(self, other, Op::Add)
+    }
+
+    /// Create a field whose values are the product of the values of this field and of `other`.
+    fn mul<'f, U: Scalar, O: Field<U>>(&'f self, other: &'f O) -> Combine<'f, Self, O, T, U>
+    where
+        Self: Sized,
+    {
+        Combine::new(self, other, Op::Mul)
+    }
+
+    /// Create a field whose values are the minimum between the values of this field and of
+    /// `other`, that is the union of the regions below the threshold.
+    fn min<'f, U: Scalar, O: Field<U>>(&'f self, other: &'f O) -> Combine<'f, Self, O, T, U>
+    where
+        Self: Sized,
+    {
+        Combine::new(self, other, Op::Min)
+    }
+
+    /// Create a field whose values are the maximum between the values of this field and of
+    /// `other`, that is the union of the regions above the threshold.
+    fn max<'f, U: Scalar, O: Field<U>>(&'f self, other: &'f O) -> Combine<'f, Self, O, T, U>
+    where
+        Self: Sized,
+    {
+        Combine::new(self, other, Op::Max)
+    }
+
+    /// Create a field whose values are the linear interpolation between the values of this field,
+    /// when `t` is 0, and the ones of `other`, when `t` is 1.
+    fn lerp<'f, U: Scalar, O: Field<U>>(
+        &'f self,
+        other: &'f O,
+        t: f64,
+    ) -> Combine<'f, Self, O, T, U>
+    where
+        Self: Sized,
+    {
+        Combine::new(self, other, Op::Lerp(t))
+    }
+
+    /// Create a view of the `width` by `height` samples of this field starting at `(x, y)`.
+    ///
+    /// Panics if the region is not entirely inside the field. Like all the other views, the
+    /// contours found on it can be mapped back to this field with `View::transform`.
+    fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> View<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        View::crop(self, (x, y), (width, height))
+    }
+
+    /// Create a view of one sample every `step` along both axes of this field, useful for quick
+    /// previews of huge fields.
+    ///
+    /// Panics if `step` is zero.
+    fn step_by(&self, step: usize) -> View<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        View::step_by(self, step)
+    }
+
+    /// Create a view of this field with the x and y axes swapped.
+    fn transpose(&self) -> View<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        View::transpose(self)
+    }
+
+    /// Create a view of this field mirrored horizontally.
+    fn flip_x(&self) -> View<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        View::flip_x(self)
+    }
+
+    /// Create a view of this field mirrored vertically.
+    fn flip_y(&self) -> View<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        View::flip_y(self)
+    }
+
+    /// Create a field that presents this field with `width` by `height` samples, calculating
+    /// the values between the samples of this field with the given interpolation.
+    fn resample(
+        &self,
+        width: usize,
+        height: usize,
+        interpolation: Interpolation,
+    ) -> Resampled<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Resampled::new(self, (width, height), interpolation)
+    }
+
+    /// Create a field whose values are the average of the samples of this field in the square
+    /// window of the given radius around them.
+    ///
+    /// Panics if `radius` is zero.
+    fn box_blur(&self, radius: usize) -> Filtered<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Filtered::box_blur(self, radius)
+    }
+
+    /// Create a field whose values are the samples of this field smoothed with a gaussian filter
+    /// with the given standard deviation.
+    ///
+    /// Panics if `sigma` is not positive.
+    fn gaussian_blur(&self, sigma: f64) -> Filtered<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Filtered::gaussian_blur(self, sigma)
+    }
+
+    /// Create a field whose values are the median of the samples of this field in the square
+    /// window of the given radius around them, which removes isolated spikes while preserving
+    /// sharp edges.
+    ///
+    /// Panics if `radius` is zero.
+    fn median_filter(&self, radius: usize) -> Filtered<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Filtered::median(self, radius)
+    }
+}
+
+/// The type of the values of a scalar field.
+pub trait Scalar: Copy {
+    /// Convert the value to `f64`, the type used to find the contours.
+    fn to_f64(self) -> f64;
 }
 
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(f32, f64, u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// The type of the coordinates of the points of the contours, see `Contours`.
+trait Coordinate: Copy + PartialEq {
+    fn from_f64(v: f64) -> Self;
+
+    fn compare(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! impl_coordinate {
+    ($($t:ty),*) => {
+        $(
+            impl Coordinate for $t {
+                fn from_f64(v: f64) -> Self {
+                    v as $t
+                }
+
+                fn compare(&self, other: &Self) -> Ordering {
+                    self.total_cmp(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_coordinate!(f32, f64);
+
 /// Contours of a shape.
-pub type Contours = Vec<Vec<(f64, f64)>>;
+///
+/// Contours are always returned in a deterministic order: they're sorted by their first point in
+/// scan order, that is by y and then by x. Closed contours, whose first and last points are the
+/// same, start at their smallest point in scan order, or at the occurrence of it that gives the
+/// smallest sequence of points when a contour passes through it more than once.
+///
+/// The points are `f64` by default, see `march_f32` to get `f32` points instead.
+pub type Contours<P = f64> = Vec<Vec<(P, P)>>;
 
-/// A `SegmentsMap` is used to speedup contour building on the average case. It's simply a map from
-/// the start position of the segment rounded with integers coordinates to the list of all the
-/// segments that start in that position. Usually, shapes have very few segments that start at the
-/// same integer position thus this simple optimization allows to find the next segment in O(1)
-/// which is great.
+/// A single contour of a scalar field.
 ///
-/// Note that a valid `SegmentsMap` must not have entries for an empty list of segments.
-type SegmentsMap = HashMap<(u64, u64), Vec<((f64, f64), (f64, f64))>>;
+/// Contours are always oriented so that the values above `level` lie on their left when the y
+/// axis points down, as it does in images. This means that closed contours around regions above
+/// the threshold are counterclockwise on screen, while the ones around regions below the threshold
+/// are clockwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    /// The points of the contour. If the contour is closed the first and last points are the same.
+    pub points: Vec<(f64, f64)>,
+
+    /// The threshold value this contour was found at.
+    pub level: f64,
+
+    /// Whether the contour is a closed loop or an open path that terminates on the boundary of
+    /// the field.
+    pub is_closed: bool,
+}
+
+/// A contour as built from the segments of the cells, together with the keys of the crossings
+/// its first and last points lie on.
+#[derive(Debug, Clone, PartialEq)]
+struct Path<K = Edge, C = f64> {
+    points: Vec<(C, C)>,
+
+    /// The keys of the first and last points, which are the same for closed contours.
+    ends: (K, K),
+}
+
+impl<K: Eq, C> Path<K, C> {
+    fn is_closed(&self) -> bool {
+        self.ends.0 == self.ends.1
+    }
+}
+
+/// The grid edge a crossing point lies on.
+///
+/// Crossing points are identified by their edge rather than by their coordinates so that segments
+/// can be joined exactly regardless of how the coordinates were calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    /// The edge going from `(x, y)` to `(x + 1, y)`.
+    Horizontal(usize, usize),
+
+    /// The edge going from `(x, y)` to `(x, y + 1)`.
+    Vertical(usize, usize),
+}
+
+/// A crossing point together with the key that identifies it.
+type Crossing<K = Edge> = (K, (f64, f64));
+
+/// A `SegmentsMap` maps the key of the start point of each segment to the start point itself, the
+/// key of the end point and the end point itself. Since each point is the start of at most one
+/// segment and it's also the end of at most another one, finding the segment that continues a
+/// contour is a simple O(1) lookup.
+type SegmentsMap<K = Edge> = HashMap<K, ((f64, f64), K, (f64, f64))>;
+
+/// How to choose the connectivity of the saddle cells, that is the cells whose diagonally opposite
+/// corners are both above or both below the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaddleResolution {
+    /// Always connect the corners above the threshold.
+    #[default]
+    Fixed,
+
+    /// Connect the corners above the threshold only if the average of the four corners, that is
+    /// the value at the center of the cell, is above the threshold too.
+    CenterSample,
+
+    /// Connect the corners above the threshold only if the saddle point of the bilinear
+    /// interpolant of the cell is above the threshold too. This is the only choice that gives a
+    /// topology consistent with the bilinear interpolation of the field.
+    AsymptoticDecider,
+}
+
+impl SaddleResolution {
+    /// Whether the corners above `z` of the saddle cell with the given z values should be
+    /// connected.
+    fn connects_above(self, [ulz, urz, brz, blz]: [f64; 4], z: f64) -> bool {
+        let center = (ulz + urz + brz + blz) / 4.0;
+
+        match self {
+            SaddleResolution::Fixed => true,
+            SaddleResolution::CenterSample => center > z,
+            SaddleResolution::AsymptoticDecider => {
+                let den = ulz + brz - urz - blz;
+                if den == 0.0 {
+                    center > z
+                } else {
+                    (ulz * brz - urz * blz) / den > z
+                }
+            }
+        }
+    }
+}
 
 /// Find the contours of a given scalar field using `z` as the threshold value.
-pub fn march(field: &impl Field, z: f64) -> Contours {
+///
+/// The output is deterministic, see `Contours` for how the contours are ordered.
+pub fn march<T: Scalar>(field: &impl Field<T>, z: f64) -> Contours {
+    march_with(field, z, SaddleResolution::Fixed)
+}
+
+/// Same as `march`, but map the contours to world coordinates with the given transform, see
+/// `Transform::apply_contours`.
+pub fn march_transformed<T: Scalar>(
+    field: &impl Field<T>,
+    z: f64,
+    transform: &transform::Transform,
+) -> Contours {
+    transform.apply_contours(march(field, z))
+}
+
+/// Same as `march`, but return the points as `f32` to halve the memory used by the contours. The
+/// points are converted while the contours are built, thus they're never stored as `f64`.
+pub fn march_f32<T: Scalar>(field: &impl Field<T>, z: f64) -> Contours<f32> {
+    build_contours(march_segments(field, z, SaddleResolution::Fixed))
+}
+
+/// Find the contours of a given scalar field using `z` as the threshold value just like `march`,
+/// but return them as `Contour`s that keep track of the threshold and of whether they're closed.
+pub fn march_contours<T: Scalar>(field: &impl Field<T>, z: f64) -> Vec<Contour> {
+    build_paths(march_segments(field, z, SaddleResolution::Fixed))
+        .into_iter()
+        .map(|path| Contour {
+            is_closed: path.is_closed(),
+            points: path.points,
+            level: z,
+        })
+        .collect()
+}
+
+/// Find the contours of a given scalar field using `z` as the threshold value and resolving the
+/// ambiguous saddle cells with the given strategy.
+pub fn march_with<T: Scalar>(field: &impl Field<T>, z: f64, saddle: SaddleResolution) -> Contours {
+    build_contours(march_segments(field, z, saddle))
+}
+
+/// Parallel version of `march` that splits the field in horizontal strips and marches them
+/// concurrently. The result is the same as the one of `march`.
+#[cfg(feature = "rayon")]
+pub fn par_march<T: Scalar>(field: &(impl Field<T> + Sync), z: f64) -> Contours {
+    par_march_with(field, z, SaddleResolution::Fixed)
+}
+
+/// Parallel version of `march_with`, see `par_march`.
+#[cfg(feature = "rayon")]
+pub fn par_march_with<T: Scalar>(
+    field: &(impl Field<T> + Sync),
+    z: f64,
+    saddle: SaddleResolution,
+) -> Contours {
+    use rayon::prelude::*;
+
     let (width, height) = field.dimensions();
+    let rows = height.saturating_sub(1);
 
-    let mut segments: SegmentsMap = HashMap::new();
-    let mut add_seg = |s: (f64, f64), e| {
-        segments
-            .entry((s.0 as u64, s.1 as u64))
-            .or_default()
-            .push((s, e));
+    // use a few strips per thread so that the work is balanced even when the contours are
+    // concentrated in a small portion of the field.
+    let strip_height = rows.div_ceil(rayon::current_num_threads() * 4).max(1);
+
+    let strips = (0..rows)
+        .step_by(strip_height)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|y| {
+            let mut segments = vec![];
+            let strip = (0..width.saturating_sub(1), y..rows.min(y + strip_height));
+            for_each_cell_in(field, strip, |xy, zs| {
+                march_cell(xy, zs, z, saddle, &mut |s, e| segments.push((s, e)));
+            });
+            segments
+        })
+        .collect::<Vec<_>>();
+
+    // stitch the strips together, the segments on the seams are then joined by build_contours
+    // as usual since they share the edges on the seams.
+    let mut segments = SegmentsMap::new();
+    for (s, e) in strips.into_iter().flatten() {
+        add_segment(&mut segments, s, e);
+    }
+
+    build_contours(segments)
+}
+
+/// Find the contours of a given scalar field for each of the given thresholds.
+///
+/// This is equivalent to calling `march` once per level, but the field is visited only once and
+/// each cell only produces the segments of the levels that actually cross it. The contours are
+/// returned in the same order as `levels`.
+pub fn march_levels<T: Scalar>(field: &impl Field<T>, levels: &[f64]) -> Vec<(f64, Contours)> {
+    // sort the levels so that the ones crossing a given cell can be found with a binary search.
+    let mut order = (0..levels.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| levels[i].total_cmp(&levels[j]));
+    let sorted_levels = order.iter().map(|&i| levels[i]).collect::<Vec<_>>();
+
+    let mut segments = vec![SegmentsMap::new(); levels.len()];
+    for_each_cell(field, |xy, zs| {
+        let min = zs.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = zs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        // a level crosses the cell only if some corner is above it and some other is not.
+        let start = sorted_levels.partition_point(|&z| z < min);
+        let end = sorted_levels.partition_point(|&z| z < max);
+
+        for (&i, &z) in order[start..end].iter().zip(&sorted_levels[start..end]) {
+            let segments = &mut segments[i];
+            march_cell(xy, zs, z, SaddleResolution::Fixed, &mut |s, e| {
+                add_segment(segments, s, e)
+            });
+        }
+    });
+
+    levels
+        .iter()
+        .zip(segments)
+        .map(|(&z, segments)| (z, build_contours(segments)))
+        .collect()
+}
+
+/// Find the segments of all the cells of the field, keyed by the edges they start on.
+fn march_segments<T: Scalar>(
+    field: &impl Field<T>,
+    z: f64,
+    saddle: SaddleResolution,
+) -> SegmentsMap {
+    let mut segments = SegmentsMap::new();
+    for_each_cell(field, |xy, zs| {
+        march_cell(xy, zs, z, saddle, &mut |s, e| {
+            add_segment(&mut segments, s, e)
+        });
+    });
+    segments
+}
+
+fn add_segment<K: Eq + Hash>(
+    segments: &mut SegmentsMap<K>,
+    (sk, s): Crossing<K>,
+    (ek, e): Crossing<K>,
+) {
+    let old = segments.insert(sk, (s, ek, e));
+    debug_assert!(old.is_none(), "two segments start at the same point");
+}
+
+/// Call `f` with the position of the upper left corner and the z values of every cell of the
+/// field, in scan order. The z values are in clockwise order starting from the upper left corner
+/// and the invalid samples have a NaN z value.
+fn for_each_cell<T: Scalar>(field: &impl Field<T>, f: impl FnMut((usize, usize), [f64; 4])) {
+    let (width, height) = field.dimensions();
+    for_each_cell_in(
+        field,
+        (0..width.saturating_sub(1), 0..height.saturating_sub(1)),
+        f,
+    );
+}
+
+/// Same as `for_each_cell`, but only visit the cells whose upper left corner is in the given
+/// ranges of columns and rows.
+fn for_each_cell_in<T: Scalar>(
+    field: &impl Field<T>,
+    (cols, rows): (Range<usize>, Range<usize>),
+    mut f: impl FnMut((usize, usize), [f64; 4]),
+) {
+    if cols.is_empty() || rows.is_empty() {
+        return;
+    }
+
+    // invalid samples are marked as NaN so that they can be handled just like NaN z values.
+    let z_at = |x, y| {
+        if field.is_valid(x, y) {
+            field.z_at(x, y).to_f64()
+        } else {
+            f64::NAN
+        }
     };
 
     // avoid calling z_at multiple times for the same cell by storing the z values for the current
     // row and by storing the values for the next row as soon as they're calculated.
-    let mut current_row_zs = (0..width).map(|x| field.z_at(x, 0)).collect::<Vec<_>>();
-    let mut next_row_zs = Vec::with_capacity(width);
+    let mut current_row_zs = (cols.start..=cols.end)
+        .map(|x| z_at(x, rows.start))
+        .collect::<Vec<_>>();
+    let mut next_row_zs = Vec::with_capacity(current_row_zs.len());
 
-    for y in 0..height.saturating_sub(1) {
+    for y in rows {
         next_row_zs.clear();
-        next_row_zs.push(field.z_at(0, y + 1));
+        next_row_zs.push(z_at(cols.start, y + 1));
 
-        for x in 0..width.saturating_sub(1) {
-            let ulz = current_row_zs[x];
-            let urz = current_row_zs[x + 1];
-            let blz = next_row_zs[x];
-            let brz = field.z_at(x + 1, y + 1);
+        for (i, x) in cols.clone().enumerate() {
+            let ulz = current_row_zs[i];
+            let urz = current_row_zs[i + 1];
+            let blz = next_row_zs[i];
+            let brz = z_at(x + 1, y + 1);
 
             next_row_zs.push(brz);
 
-            let mut case = 0;
-            if blz > z {
-                case |= 1;
-            }
-            if brz > z {
-                case |= 2;
-            }
-            if urz > z {
-                case |= 4;
-            }
-            if ulz > z {
-                case |= 8;
-            }
-            let x = x as f64;
-            let y = y as f64;
+            f((x, y), [ulz, urz, brz, blz]);
+        }
 
-            let top = (x + fraction(z, (ulz, urz)), y);
-            let bottom = (x + fraction(z, (blz, brz)), y + 1.0);
-            let left = (x, y + fraction(z, (ulz, blz)));
-            let right = (x + 1.0, y + fraction(z, (urz, brz)));
+        std::mem::swap(&mut current_row_zs, &mut next_row_zs);
+    }
+}
 
-            match case {
-                0 | 15 => {}
-                1 => {
-                    add_seg(bottom, left);
-                }
-                2 => {
-                    add_seg(right, bottom);
-                }
-                3 => {
-                    add_seg(right, left);
-                }
-                4 => {
-                    add_seg(top, right);
-                }
-                5 => {
-                    add_seg(top, left);
-                    add_seg(bottom, right);
-                }
-                6 => {
-                    add_seg(top, bottom);
-                }
-                7 => {
-                    add_seg(top, left);
-                }
-                8 => {
-                    add_seg(left, top);
-                }
-                9 => {
-                    add_seg(bottom, top);
-                }
-                10 => {
-                    add_seg(left, bottom);
-                    add_seg(right, top);
-                }
-                11 => {
-                    add_seg(right, top);
-                }
-                12 => {
-                    add_seg(left, right);
-                }
-                13 => {
-                    add_seg(bottom, right);
-                }
-                14 => {
-                    add_seg(left, bottom);
-                }
-                _ => unreachable!(),
+/// Add the segments crossing the cell whose upper left corner is at `(x, y)` and whose corners
+/// have the given z values in clockwise order starting from the upper left one.
+///
+/// Segments are oriented so that the values above `z` lie on their left when the y axis points
+/// down. Cells with a NaN corner are skipped.
+fn march_cell(
+    (x, y): (usize, usize),
+    [ulz, urz, brz, blz]: [f64; 4],
+    z: f64,
+    saddle: SaddleResolution,
+    add_seg: &mut impl FnMut(Crossing, Crossing),
+) {
+    if is_invalid_cell([ulz, urz, brz, blz]) {
+        return;
+    }
+
+    let mut case = 0;
+    if blz > z {
+        case |= 1;
+    }
+    if brz > z {
+        case |= 2;
+    }
+    if urz > z {
+        case |= 4;
+    }
+    if ulz > z {
+        case |= 8;
+    }
+
+    if case == 0 || case == 15 {
+        return;
+    }
+
+    let [top, right, bottom, left] = cell_crossings((x, y), [ulz, urz, brz, blz], z);
+
+    match case {
+        1 => {
+            add_seg(bottom, left);
+        }
+        2 => {
+            add_seg(right, bottom);
+        }
+        3 => {
+            add_seg(right, left);
+        }
+        4 => {
+            add_seg(top, right);
+        }
+        5 => {
+            if saddle.connects_above([ulz, urz, brz, blz], z) {
+                add_seg(top, left);
+                add_seg(bottom, right);
+            } else {
+                add_seg(bottom, left);
+                add_seg(top, right);
             }
         }
-
-        std::mem::swap(&mut current_row_zs, &mut next_row_zs);
+        6 => {
+            add_seg(top, bottom);
+        }
+        7 => {
+            add_seg(top, left);
+        }
+        8 => {
+            add_seg(left, top);
+        }
+        9 => {
+            add_seg(bottom, top);
+        }
+        10 => {
+            if saddle.connects_above([ulz, urz, brz, blz], z) {
+                add_seg(left, bottom);
+                add_seg(right, top);
+            } else {
+                add_seg(left, top);
+                add_seg(right, bottom);
+            }
+        }
+        11 => {
+            add_seg(right, top);
+        }
+        12 => {
+            add_seg(left, right);
+        }
+        13 => {
+            add_seg(bottom, right);
+        }
+        14 => {
+            add_seg(left, bottom);
+        }
+        _ => unreachable!(),
     }
+}
+
+fn is_invalid_cell(zs: [f64; 4]) -> bool {
+    zs.iter().any(|z| z.is_nan())
+}
 
-    build_contours(segments, (width as u64, height as u64))
+/// Calculate where the threshold `z` crosses the top, right, bottom and left edges of the cell
+/// whose upper left corner is at `(x, y)`.
+fn cell_crossings((x, y): (usize, usize), [ulz, urz, brz, blz]: [f64; 4], z: f64) -> [Crossing; 4] {
+    let (xf, yf) = (x as f64, y as f64);
+
+    [
+        (Edge::Horizontal(x, y), (xf + fraction(z, (ulz, urz)), yf)),
+        (
+            Edge::Vertical(x + 1, y),
+            (xf + 1.0, yf + fraction(z, (urz, brz))),
+        ),
+        (
+            Edge::Horizontal(x, y + 1),
+            (xf + fraction(z, (blz, brz)), yf + 1.0),
+        ),
+        (Edge::Vertical(x, y), (xf, yf + fraction(z, (ulz, blz)))),
+    ]
 }
 
-fn build_contours(mut segments: SegmentsMap, (w, h): (u64, u64)) -> Contours {
-    use std::collections::hash_map::Entry;
+/// Join the segments in `segments` into contours, see `build_paths`.
+fn build_contours<K: Copy + Eq + Hash, C: Coordinate>(segments: SegmentsMap<K>) -> Contours<C> {
+    build_paths(segments)
+        .into_iter()
+        .map(|path| path.points)
+        .collect()
+}
 
-    let mut contours = vec![];
+/// Join the segments in `segments` into normalized paths whose points are converted to the given
+/// coordinate type.
+///
+/// Open contours start at the points that are not the end of any segment, that is on the boundary
+/// of the field, and then all the remaining segments form closed contours.
+fn build_paths<K: Copy + Eq + Hash, C: Coordinate>(
+    mut segments: SegmentsMap<K>,
+) -> Vec<Path<K, C>> {
+    let point = |(x, y): (f64, f64)| (C::from_f64(x), C::from_f64(y));
 
-    let mut boundaries = segments
-        .keys()
-        .cloned()
-        .filter(|s| s.0 == 0 || s.0 == w - 1 || s.1 == 0 || s.1 == h - 1)
+    let ends = segments
+        .values()
+        .map(|(_, k, _)| *k)
         .collect::<HashSet<_>>();
 
-    while !segments.is_empty() {
-        // prefer to start on a boundary, but if no point lie on a bounday just
-        // pick a random one. This allows to connect open paths entirely without
-        // breaking them in multiple chunks.
-        let first_k = boundaries
-            .iter()
-            .next()
-            .map_or_else(|| *segments.keys().next().unwrap(), |k| *k);
-
-        let mut first_e = match segments.entry(first_k) {
-            Entry::Occupied(o) => o,
-            Entry::Vacant(_) => unreachable!(),
+    let (mut starts, rest): (Vec<_>, Vec<_>) =
+        segments.keys().copied().partition(|k| !ends.contains(k));
+    starts.extend(rest);
+
+    let mut paths = vec![];
+    for first_k in starts {
+        let Some((s, mut k, e)) = segments.remove(&first_k) else {
+            continue;
         };
 
-        let first = first_e.get_mut().pop().unwrap();
-        if first_e.get().is_empty() {
-            first_e.remove_entry();
-            boundaries.remove(&first_k);
+        let mut points = vec![point(s), point(e)];
+        while let Some((_, next_k, e)) = segments.remove(&k) {
+            points.push(point(e));
+            k = next_k;
         }
 
-        let mut contour = vec![first.0, first.1];
+        // make sure closed contours are closed exactly, no matter how the points were calculated
+        if k == first_k {
+            let last = points.len() - 1;
+            points[last] = points[0];
+        }
 
-        loop {
-            let prev = contour[contour.len() - 1];
+        paths.push(Path {
+            points,
+            ends: (first_k, k),
+        });
+    }
 
-            let segments_k = (prev.0 as u64, prev.1 as u64);
-            let mut segments = match segments.entry(segments_k) {
-                Entry::Vacant(_) => break,
-                Entry::Occupied(o) => o,
-            };
+    normalize_paths(&mut paths);
+    paths
+}
 
-            let next = segments
-                .get()
-                .iter()
-                .enumerate()
-                .find(|(_, (s, _))| s == &prev);
-
-            match next {
-                None => break,
-                Some((i, seg)) => {
-                    contour.push(seg.1);
-
-                    segments.get_mut().swap_remove(i);
-                    if segments.get().is_empty() {
-                        segments.remove_entry();
-                        boundaries.remove(&segments_k);
-                    }
-                }
+/// Make the order of the paths and the starting point of the closed ones independent of how they
+/// were built: closed paths are rotated so that their points are the smallest sequence in scan
+/// order and then all the paths are sorted by their points in scan order.
+fn normalize_paths<K: Eq, C: Coordinate>(paths: &mut [Path<K, C>]) {
+    for path in paths.iter_mut() {
+        if !path.is_closed() {
+            continue;
+        }
+
+        let contour = &mut path.points;
+        contour.pop();
+
+        // the smallest point can appear more than once when the contour touches a sample, in that
+        // case compare the whole rotations starting at each occurrence.
+        let rotation = |i: usize| contour[i..].iter().chain(&contour[..i]);
+        let start = (1..contour.len()).fold(0, |best, i| {
+            if compare_points(rotation(i), rotation(best)).is_lt() {
+                i
+            } else {
+                best
             }
+        });
+
+        contour.rotate_left(start);
+        contour.push(contour[0]);
+    }
+
+    paths.sort_by(|a, b| compare_points(&a.points, &b.points));
+}
+
+/// Compare two sequences of points lexicographically in scan order.
+fn compare_points<'a, C: Coordinate + 'a>(
+    a: impl IntoIterator<Item = &'a (C, C)>,
+    b: impl IntoIterator<Item = &'a (C, C)>,
+) -> Ordering {
+    let mut b = b.into_iter();
+
+    for a in a {
+        match b.next() {
+            Some(b) => match scan_order(a, b) {
+                Ordering::Equal => continue,
+                o => return o,
+            },
+            None => return Ordering::Greater,
         }
+    }
 
-        contours.push(contour);
+    if b.next().is_some() {
+        Ordering::Less
+    } else {
+        Ordering::Equal
     }
+}
 
-    contours
+/// Compare two points by y and then by x.
+fn scan_order<C: Coordinate>(a: &(C, C), b: &(C, C)) -> Ordering {
+    a.1.compare(&b.1).then(a.0.compare(&b.0))
 }
 
 fn fraction(z: f64, (z0, z1): (f64, f64)) -> f64 {
@@ -223,12 +879,13 @@ fn fraction(z: f64, (z0, z1): (f64, f64)) -> f64 {
 }
 
 #[derive(Debug, Clone)]
-pub struct Framed<'s, F> {
+pub struct Framed<'s, F, T = f64> {
     field: &'s F,
     border_z: f64,
+    _value: PhantomData<T>,
 }
 
-impl<T: Field> Field for Framed<'_, T> {
+impl<T: Scalar, F: Field<T>> Field for Framed<'_, F, T> {
     fn dimensions(&self) -> (usize, usize) {
         self.field.dimensions()
     }
@@ -239,9 +896,19 @@ impl<T: Field> Field for Framed<'_, T> {
         if x == 0 || x == w.saturating_sub(1) || y == 0 || y == h.saturating_sub(1) {
             self.border_z + 1e-9
         } else {
-            self.field.z_at(x, y)
+            self.field.z_at(x, y).to_f64()
         }
     }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        let (w, h) = self.dimensions();
+
+        x == 0
+            || x == w.saturating_sub(1)
+            || y == 0
+            || y == h.saturating_sub(1)
+            || self.field.is_valid(x, y)
+    }
 }
 
 #[cfg(test)]
@@ -293,6 +960,246 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_msq_deterministic_order() {
+        struct Squares;
+        impl Field for Squares {
+            fn dimensions(&self) -> (usize, usize) {
+                (10, 10)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                let a = (6..8).contains(&x) && (2..4).contains(&y);
+                let b = (2..4).contains(&x) && (6..8).contains(&y);
+                if a || b {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+        let contours = march(&Squares, 0.5)
+            .into_iter()
+            .map(|p| simplify(&p))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            contours,
+            vec![
+                vec![
+                    (6.0, 1.5),
+                    (5.5, 2.0),
+                    (5.5, 3.0),
+                    (6.0, 3.5),
+                    (7.0, 3.5),
+                    (7.5, 3.0),
+                    (7.5, 2.0),
+                    (7.0, 1.5),
+                    (6.0, 1.5)
+                ],
+                vec![
+                    (2.0, 5.5),
+                    (1.5, 6.0),
+                    (1.5, 7.0),
+                    (2.0, 7.5),
+                    (3.0, 7.5),
+                    (3.5, 7.0),
+                    (3.5, 6.0),
+                    (3.0, 5.5),
+                    (2.0, 5.5)
+                ]
+            ]
+        );
+    }
+
+    /// A smooth field with several hills and valleys of the given size, whose contours cross each
+    /// other's cells in many different ways.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct Waves(pub(crate) usize, pub(crate) usize);
+
+    impl Field for Waves {
+        fn dimensions(&self) -> (usize, usize) {
+            (self.0, self.1)
+        }
+
+        fn z_at(&self, x: usize, y: usize) -> f64 {
+            let (x, y) = (x as f64 / 5.0, y as f64 / 5.0);
+            x.sin() * y.cos() + (x * y / 20.0).cos()
+        }
+    }
+
+    /// A pseudo random field of small integers, so that many samples are exactly at integer
+    /// thresholds.
+    pub(crate) fn integer_noise(width: usize, height: usize, seed: u64) -> grid::Grid<u8> {
+        let mut state = seed;
+        let samples = (0..width * height)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 60) as u8 % 5
+            })
+            .collect();
+
+        grid::Grid::new(width, height, samples)
+    }
+
+    #[test]
+    fn test_msq_deterministic_with_samples_at_threshold() {
+        for seed in 0..20 {
+            let grid = integer_noise(20, 20, seed);
+            let expected = march(&grid, 2.0);
+
+            for _ in 0..10 {
+                assert_eq!(march(&grid, 2.0), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_march_contours() {
+        struct Ramp;
+        impl Field for Ramp {
+            fn dimensions(&self) -> (usize, usize) {
+                (5, 4)
+            }
+
+            fn z_at(&self, x: usize, _y: usize) -> f64 {
+                x as f64
+            }
+        }
+
+        // the values above the threshold are on the right of the line, so it must go down.
+        assert_eq!(
+            march_contours(&Ramp, 2.5),
+            vec![Contour {
+                points: vec![(2.5, 0.0), (2.5, 1.0), (2.5, 2.0), (2.5, 3.0)],
+                level: 2.5,
+                is_closed: false,
+            }]
+        );
+
+        let signed_area = |c: &Contour| {
+            c.points
+                .windows(2)
+                .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+                .sum::<f64>()
+        };
+
+        // a closed contour around the values above the threshold is counterclockwise when the y
+        // axis points down, that is its signed area is negative.
+        let contours = march_contours(&Ramp.framed(-1.0), 1.5);
+        assert_eq!(contours.len(), 1);
+        assert!(contours[0].is_closed);
+        assert!(signed_area(&contours[0]) < 0.0);
+
+        // while a closed contour around values below the threshold is clockwise.
+        let contours = march_contours(&Ramp.framed(1.5), 1.5);
+        assert_eq!(contours.len(), 1);
+        assert!(contours[0].is_closed);
+        assert!(signed_area(&contours[0]) > 0.0);
+
+        // an open path that starts and ends at the same sample on the boundary is still open.
+        let grid = grid::Grid::new(3, 2, vec![1.0, 0.5, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(
+            march_contours(&grid, 0.5),
+            vec![Contour {
+                points: vec![(1.0, 0.0), (1.0, 0.0), (1.0, 0.0)],
+                level: 0.5,
+                is_closed: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_msq_open_path_going_up() {
+        struct LeftColumn;
+        impl Field for LeftColumn {
+            fn dimensions(&self) -> (usize, usize) {
+                (4, 6)
+            }
+
+            fn z_at(&self, x: usize, _y: usize) -> f64 {
+                if x == 0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+        assert_eq!(
+            march(&LeftColumn, 0.5),
+            vec![vec![
+                (0.5, 5.0),
+                (0.5, 4.0),
+                (0.5, 3.0),
+                (0.5, 2.0),
+                (0.5, 1.0),
+                (0.5, 0.0)
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_msq_invalid_samples() {
+        struct Holey;
+        impl Field for Holey {
+            fn dimensions(&self) -> (usize, usize) {
+                (8, 8)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                if (x, y) == (5, 4) {
+                    return f64::NAN;
+                }
+
+                let r = 2..6;
+                if r.contains(&x) && r.contains(&y) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+
+            fn is_valid(&self, x: usize, y: usize) -> bool {
+                (x, y) != (2, 3)
+            }
+        }
+
+        let contours = march_contours(&Holey, 0.5);
+        assert_eq!(contours.len(), 2);
+        assert!(contours.iter().all(|c| !c.is_closed));
+
+        // the square is split in two open paths that stop right before the cells touching the
+        // invalid samples.
+        assert_eq!(
+            contours[0].points,
+            vec![
+                (5.5, 3.0),
+                (5.5, 2.0),
+                (5.0, 1.5),
+                (4.0, 1.5),
+                (3.0, 1.5),
+                (2.0, 1.5),
+                (1.5, 2.0)
+            ]
+        );
+        assert_eq!(
+            contours[1].points,
+            vec![
+                (1.5, 4.0),
+                (1.5, 5.0),
+                (2.0, 5.5),
+                (3.0, 5.5),
+                (4.0, 5.5),
+                (5.0, 5.5),
+                (5.5, 5.0)
+            ]
+        );
+    }
+
     #[test]
     fn test_msq_everything_filled() {
         struct Filled;
@@ -326,6 +1233,186 @@ mod tests {
         assert_eq!(march(&Empty {}.framed(2.0), 2.0).len(), 1);
     }
 
+    #[test]
+    fn test_march_levels() {
+        struct Pyramid;
+        impl Field for Pyramid {
+            fn dimensions(&self) -> (usize, usize) {
+                (9, 9)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                let d = x.abs_diff(4).max(y.abs_diff(4));
+                4.0 - d as f64
+            }
+        }
+
+        let levels = [2.5, 0.5, 10.0, 1.5, -1.0];
+        let contours = march_levels(&Pyramid, &levels);
+
+        assert_eq!(contours.len(), levels.len());
+        for (&z, (cz, contours)) in levels.iter().zip(contours) {
+            assert_eq!(z, cz);
+            assert_eq!(contours, march(&Pyramid, z));
+        }
+    }
+
+    #[test]
+    fn test_saddle_resolution() {
+        struct Cell([f64; 4]);
+        impl Field for Cell {
+            fn dimensions(&self) -> (usize, usize) {
+                (2, 2)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                let [ulz, urz, brz, blz] = self.0;
+                match (x, y) {
+                    (0, 0) => ulz,
+                    (1, 0) => urz,
+                    (1, 1) => brz,
+                    _ => blz,
+                }
+            }
+        }
+
+        let sorted = |mut contours: Contours| {
+            contours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            contours
+        };
+
+        let check = |zs, above_connected: [bool; 3]| {
+            let [top, right, bottom, left] = cell_crossings((0, 0), zs, 0.5).map(|(_, p)| p);
+
+            let (above, below) = if zs[0] > 0.5 {
+                (
+                    vec![vec![left, bottom], vec![right, top]],
+                    vec![vec![left, top], vec![right, bottom]],
+                )
+            } else {
+                (
+                    vec![vec![top, left], vec![bottom, right]],
+                    vec![vec![bottom, left], vec![top, right]],
+                )
+            };
+
+            let saddles = [
+                SaddleResolution::Fixed,
+                SaddleResolution::CenterSample,
+                SaddleResolution::AsymptoticDecider,
+            ];
+            for (saddle, above_connected) in saddles.into_iter().zip(above_connected) {
+                let expected = if above_connected { &above } else { &below };
+                assert_eq!(
+                    sorted(march_with(&Cell(zs), 0.5, saddle)),
+                    sorted(expected.clone()),
+                    "{:?} {:?}",
+                    zs,
+                    saddle
+                );
+            }
+        };
+
+        // the center is above the threshold, but the saddle of the bilinear interpolant is not.
+        check([0.0, 0.52, 0.3, 1.2], [true, true, false]);
+        check([0.52, 0.0, 1.2, 0.3], [true, true, false]);
+
+        // both the center and the saddle are below the threshold
+        check([0.0, 0.6, 0.2, 0.6], [true, false, false]);
+        check([0.6, 0.0, 0.6, 0.2], [true, false, false]);
+
+        // both the center and the saddle are above the threshold
+        check([0.0, 1.0, 0.4, 1.0], [true, true, true]);
+        check([1.0, 0.0, 1.0, 0.4], [true, true, true]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_march() {
+        let waves = Waves(123, 457);
+        for z in [-1.0, -0.3, 0.0, 0.5, 1.2] {
+            assert_eq!(par_march(&waves, z), march(&waves, z));
+        }
+
+        for seed in 0..20 {
+            let grid = integer_noise(30, 40, seed);
+            assert_eq!(par_march(&grid, 2.0), march(&grid, 2.0));
+        }
+    }
+
+    #[test]
+    fn test_scalar_types() {
+        struct Pyramid;
+        impl Field for Pyramid {
+            fn dimensions(&self) -> (usize, usize) {
+                (9, 9)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                let d = x.abs_diff(4).max(y.abs_diff(4));
+                4.0 - d as f64
+            }
+        }
+
+        struct Bytes;
+        impl Field<u8> for Bytes {
+            fn dimensions(&self) -> (usize, usize) {
+                Pyramid.dimensions()
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> u8 {
+                Pyramid.z_at(x, y) as u8
+            }
+        }
+
+        struct Floats;
+        impl Field<f32> for Floats {
+            fn dimensions(&self) -> (usize, usize) {
+                Pyramid.dimensions()
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f32 {
+                Pyramid.z_at(x, y) as f32
+            }
+        }
+
+        for z in [0.5, 1.5, 2.5, 3.75] {
+            let expected = march(&Pyramid, z);
+            assert_eq!(march(&Bytes, z), expected);
+            assert_eq!(march(&Floats, z), expected);
+            assert_eq!(march(&Bytes.framed(z), z), march(&Pyramid.framed(z), z));
+
+            let points = march_f32(&Bytes, z);
+            assert_eq!(points.len(), expected.len());
+            for (c, e) in points.iter().zip(&expected) {
+                assert!(c.iter().zip(e).all(|(p, e)| *p == (e.0 as f32, e.1 as f32)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let grid = grid::Grid::new(3, 2, vec![0.0, 2.0, 4.0, 4.0, 6.0, 8.0]);
+
+        assert_eq!(grid.sample(1.0, 1.0), 6.0);
+        assert_eq!(grid.sample(0.5, 0.0), 1.0);
+        assert_eq!(grid.sample(1.5, 0.5), 5.0);
+
+        // positions outside the field are clamped to it
+        assert_eq!(grid.sample(-1.0, 5.0), 4.0);
+        assert_eq!(grid.sample(2.5, 0.5), 6.0);
+
+        // the samples with no weight are not read, thus NaN samples next to an edge don't matter
+        let grid = grid::Grid::new(2, 2, vec![0.0, 2.0, f64::NAN, f64::NAN]);
+        assert_eq!(grid.sample(0.5, 0.0), 1.0);
+
+        let empty = grid::Grid::<f64>::new(0, 0, vec![]);
+        assert!(empty.sample(0.0, 0.0).is_nan());
+        assert!(grid::Grid::<f64>::new(3, 0, vec![])
+            .sample(1.0, 0.0)
+            .is_nan());
+    }
+
     #[test]
     fn test_fraction() {
         assert_eq!(fraction(5.0, (5.0, 5.0)), 0.5);
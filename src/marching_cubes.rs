@@ -0,0 +1,342 @@
+//! Find the isosurfaces of 3D scalar fields, like voxel volumes or signed distance fields.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::{fraction, Scalar};
+
+/// A 3D scalar field whose values are of type `T`, `f64` by default. It's the 3D counterpart of
+/// `Field`.
+pub trait Field3<T: Scalar = f64> {
+    /// Get the width, height and depth of the scalar field.
+    fn dimensions(&self) -> (usize, usize, usize);
+
+    /// Calculate the value at the given position. The position is always inside the range of
+    /// `dimensions`.
+    fn value_at(&self, x: usize, y: usize, z: usize) -> T;
+
+    /// Whether the sample at the given position holds valid data. The cubes that touch an
+    /// invalid sample, or a sample whose value is NaN, are skipped.
+    ///
+    /// By default every sample is valid.
+    fn is_valid(&self, _x: usize, _y: usize, _z: usize) -> bool {
+        true
+    }
+}
+
+/// An indexed triangle mesh.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    /// The positions of the vertices.
+    pub vertices: Vec<(f64, f64, f64)>,
+
+    /// The triangles of the mesh, made of the indices of their vertices.
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    /// Write the mesh in the Wavefront OBJ format.
+    pub fn write_obj(&self, mut out: impl Write) -> io::Result<()> {
+        for (x, y, z) in &self.vertices {
+            writeln!(out, "v {} {} {}", x, y, z)?;
+        }
+
+        // OBJ indices start from 1
+        for [a, b, c] in &self.triangles {
+            writeln!(out, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the mesh in the ASCII PLY format.
+    pub fn write_ply(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "ply")?;
+        writeln!(out, "format ascii 1.0")?;
+        writeln!(out, "element vertex {}", self.vertices.len())?;
+        writeln!(out, "property double x")?;
+        writeln!(out, "property double y")?;
+        writeln!(out, "property double z")?;
+        writeln!(out, "element face {}", self.triangles.len())?;
+        writeln!(out, "property list uchar uint vertex_indices")?;
+        writeln!(out, "end_header")?;
+
+        for (x, y, z) in &self.vertices {
+            writeln!(out, "{} {} {}", x, y, z)?;
+        }
+
+        for [a, b, c] in &self.triangles {
+            writeln!(out, "3 {} {} {}", a, b, c)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A sample of the field.
+type Point = (usize, usize, usize);
+
+/// The six tetrahedra each cube is split into, as offsets of their vertices from the origin of
+/// the cube. They all share the main diagonal of the cube and each face of the cube is split
+/// along the diagonal going from its smallest corner, so that the faces of adjacent cubes are
+/// split in the same way.
+const TETRAHEDRA: [[Point; 4]; 6] = [
+    [(0, 0, 0), (1, 0, 0), (1, 1, 0), (1, 1, 1)],
+    [(0, 0, 0), (1, 0, 0), (1, 0, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 1, 0), (1, 1, 0), (1, 1, 1)],
+    [(0, 0, 0), (0, 1, 0), (0, 1, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 0, 1), (1, 0, 1), (1, 1, 1)],
+    [(0, 0, 0), (0, 0, 1), (0, 1, 1), (1, 1, 1)],
+];
+
+/// Find the isosurface of the given 3D scalar field at the given `level`.
+///
+/// Each cube of the field is split in six tetrahedra whose isosurface is found exactly like
+/// `march` does for the cells of a 2D field, which avoids the ambiguous cases of the classic
+/// marching cubes and guarantees the surface has no cracks. The vertices of the mesh are shared
+/// between adjacent cubes and the triangles are wound counterclockwise when seen from the side
+/// below `level`, that is their normals point away from the region above `level`. When some
+/// samples are exactly at `level` some triangles have zero area, but they're still wound
+/// consistently with their neighbors.
+pub fn march_cubes<T: Scalar>(field: &impl Field3<T>, level: f64) -> Mesh {
+    let (width, height, depth) = field.dimensions();
+
+    let mut mesh = Mesh::default();
+    if width < 2 || height < 2 || depth < 2 {
+        return mesh;
+    }
+
+    // invalid samples are marked as NaN so that they can be handled just like NaN values.
+    let slice = |z| {
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if field.is_valid(x, y, z) {
+                    field.value_at(x, y, z).to_f64()
+                } else {
+                    f64::NAN
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // the indices of the vertices lying on the edge between two samples, with the smallest
+    // sample first.
+    let mut vertices = HashMap::<(Point, Point), usize>::new();
+
+    let mut current_slice = slice(0);
+    for z in 0..depth - 1 {
+        let next_slice = slice(z + 1);
+
+        let value = |(x, y, dz): Point| {
+            let s = if dz == 0 { &current_slice } else { &next_slice };
+            s[y * width + x]
+        };
+
+        for y in 0..height - 1 {
+            for x in 0..width - 1 {
+                let corners = (0..8).map(|i| (x + (i & 1), y + ((i >> 1) & 1), i >> 2));
+                if corners.map(value).any(f64::is_nan) {
+                    continue;
+                }
+
+                for tetrahedron in &TETRAHEDRA {
+                    let points = tetrahedron.map(|(dx, dy, dz)| (x + dx, y + dy, z + dz));
+                    let values = tetrahedron.map(|(dx, dy, dz)| value((x + dx, y + dy, dz)));
+
+                    march_tetrahedron(points, values, level, &mut |edges, high| {
+                        let [a, b, c] = edges;
+                        let mut vertex = |(p, q): (Point, Point)| {
+                            let (p, q) = (p.min(q), p.max(q));
+                            *vertices.entry((p, q)).or_insert_with(|| {
+                                let t = fraction(
+                                    level,
+                                    (value(local(p, (x, y, z))), value(local(q, (x, y, z)))),
+                                );
+                                mesh.vertices.push(lerp(p, q, t));
+                                mesh.vertices.len() - 1
+                            })
+                        };
+
+                        let mut triangle = [vertex(a), vertex(b), vertex(c)];
+
+                        // orient the triangle so that its normal points away from the region
+                        // above the threshold. The vertices can coincide when the samples are
+                        // exactly at the threshold, thus use the midpoints of the edges, that
+                        // give a triangle with the same orientation that is never degenerate.
+                        let [pa, pb, pc] = edges.map(|(p, q)| lerp(p, q, 0.5));
+                        let normal = cross(sub(pb, pa), sub(pc, pa));
+                        let high = (high.0 as f64, high.1 as f64, high.2 as f64);
+                        if dot(normal, sub(high, pa)) > 0.0 {
+                            triangle.swap(1, 2);
+                        }
+
+                        mesh.triangles.push(triangle);
+                    });
+                }
+            }
+        }
+
+        current_slice = next_slice;
+    }
+
+    mesh
+}
+
+/// Call `add_triangle` with the edges the vertices of each triangle of the isosurface of the
+/// given tetrahedron lie on, together with a vertex above `level`.
+fn march_tetrahedron(
+    points: [Point; 4],
+    values: [f64; 4],
+    level: f64,
+    add_triangle: &mut impl FnMut([(Point, Point); 3], Point),
+) {
+    let (above, below): (Vec<_>, Vec<_>) = (0..4).partition(|&i| values[i] > level);
+    let edge = |i: usize, j: usize| (points[i], points[j]);
+
+    match (above.as_slice(), below.as_slice()) {
+        ([a], [b, c, d]) => add_triangle([edge(*a, *b), edge(*a, *c), edge(*a, *d)], points[*a]),
+        ([a, b, c], [d]) => add_triangle([edge(*d, *a), edge(*d, *b), edge(*d, *c)], points[*a]),
+        ([a, b], [c, d]) => {
+            // the four vertices form a quad, split it in two triangles
+            let quad = [edge(*a, *c), edge(*a, *d), edge(*b, *d), edge(*b, *c)];
+            add_triangle([quad[0], quad[1], quad[2]], points[*a]);
+            add_triangle([quad[0], quad[2], quad[3]], points[*a]);
+        }
+        _ => {}
+    }
+}
+
+/// Convert the given sample to the local coordinates used to look up its value, that is with the
+/// z coordinate relative to the slice starting at `z`.
+fn local((x, y, z): Point, (_, _, z0): Point) -> Point {
+    (x, y, z - z0)
+}
+
+fn lerp(p: Point, q: Point, t: f64) -> (f64, f64, f64) {
+    let l = |a: usize, b: usize| a as f64 + (b as f64 - a as f64) * t;
+    (l(p.0, q.0), l(p.1, q.1), l(p.2, q.2))
+}
+
+fn sub(a: (f64, f64, f64), b: (f64, f64, f64)) -> (f64, f64, f64) {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn cross(a: (f64, f64, f64), b: (f64, f64, f64)) -> (f64, f64, f64) {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+fn dot(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::tests::integer_noise;
+    use crate::Field;
+
+    struct Ball;
+    impl Field3 for Ball {
+        fn dimensions(&self) -> (usize, usize, usize) {
+            (12, 13, 14)
+        }
+
+        fn value_at(&self, x: usize, y: usize, z: usize) -> f64 {
+            let (x, y, z) = (x as f64 - 5.5, y as f64 - 6.0, z as f64 - 6.5);
+            4.0 - (x * x + y * y + z * z).sqrt()
+        }
+    }
+
+    /// Check that the surface is closed and consistently oriented: each directed edge appears
+    /// exactly once and its opposite appears too.
+    fn assert_closed(mesh: &Mesh) {
+        let mut edges = HashMap::new();
+        for &[a, b, c] in &mesh.triangles {
+            for e in [(a, b), (b, c), (c, a)] {
+                *edges.entry(e).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &n) in &edges {
+            assert_eq!(n, 1);
+            assert_eq!(edges.get(&(b, a)), Some(&1));
+        }
+    }
+
+    #[test]
+    fn test_march_cubes_ball() {
+        let mesh = march_cubes(&Ball, 0.0);
+        assert!(!mesh.triangles.is_empty());
+        assert_closed(&mesh);
+
+        // the normals point outwards, thus the volume is positive
+        let volume = mesh
+            .triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|i| mesh.vertices[i]);
+                dot(a, cross(b, c)) / 6.0
+            })
+            .sum::<f64>();
+        let expected = 4.0 / 3.0 * std::f64::consts::PI * 4.0_f64.powi(3);
+        assert!(volume > 0.0);
+        assert!((volume - expected).abs() / expected < 0.05);
+    }
+
+    #[test]
+    fn test_march_cubes_samples_at_level() {
+        struct Noise(Grid<u8>);
+        impl Field3 for Noise {
+            fn dimensions(&self) -> (usize, usize, usize) {
+                (7, 6, 5)
+            }
+
+            fn value_at(&self, x: usize, y: usize, z: usize) -> f64 {
+                // pad the volume so that the surface is closed
+                if x == 0 || y == 0 || z == 0 || x == 6 || y == 5 || z == 4 {
+                    -1.0
+                } else {
+                    f64::from(self.0.z_at(x, y * 5 + z) % 3)
+                }
+            }
+        }
+
+        for seed in 0..50 {
+            let mesh = march_cubes(&Noise(integer_noise(7, 30, seed)), 1.0);
+            assert!(!mesh.triangles.is_empty());
+            assert_closed(&mesh);
+        }
+    }
+
+    #[test]
+    fn test_march_cubes_empty() {
+        assert_eq!(march_cubes(&Ball, 10.0), Mesh::default());
+        assert_eq!(march_cubes(&Ball, -10.0), Mesh::default());
+    }
+
+    #[test]
+    fn test_mesh_writers() {
+        let mesh = Mesh {
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.5, 0.0)],
+            triangles: vec![[0, 1, 2]],
+        };
+
+        let mut obj = vec![];
+        mesh.write_obj(&mut obj).unwrap();
+        assert_eq!(
+            String::from_utf8(obj).unwrap(),
+            "v 0 0 0\nv 1 0 0\nv 0 1.5 0\nf 1 2 3\n"
+        );
+
+        let mut ply = vec![];
+        mesh.write_ply(&mut ply).unwrap();
+        let ply = String::from_utf8(ply).unwrap();
+        assert!(ply.starts_with("ply\nformat ascii 1.0\nelement vertex 3\n"));
+        assert!(ply.ends_with("end_header\n0 0 0\n1 0 0\n0 1.5 0\n3 0 1 2\n"));
+    }
+}
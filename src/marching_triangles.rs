@@ -0,0 +1,143 @@
+//! Find the contours of scalar fields sampled at scattered points and triangulated into a
+//! triangulated irregular network, aka TIN, rather than sampled on a regular grid.
+
+use crate::{add_segment, build_contours, fraction, Contours, SegmentsMap};
+
+/// The edge of the mesh a crossing point lies on, identified by the indices of its vertices with
+/// the smallest one first.
+type MeshEdge = (usize, usize);
+
+/// Find the contours of the triangulated irregular network made of the given vertices, whose
+/// coordinates are `(x, y, z)`, and triangles, made of the indices of their vertices, using `z`
+/// as the threshold value.
+///
+/// The contours have the same guarantees as the ones returned by `march`: they're oriented so
+/// that the values above the threshold lie on their left when the y axis points down, closed
+/// contours are closed exactly and their order is deterministic. The triangles can be wound
+/// either way, but they must not overlap and two adjacent triangles must share the whole edge
+/// for the contours crossing them to be joined. The triangles touching a vertex whose z value is
+/// NaN are skipped.
+///
+/// Panics if a triangle refers to a vertex that doesn't exist.
+pub fn march_triangles(vertices: &[(f64, f64, f64)], triangles: &[[usize; 3]], z: f64) -> Contours {
+    let mut segments = SegmentsMap::new();
+
+    // the point where the contour crosses the edge going from vertex i to vertex j. The vertices
+    // are always taken in the same order so that adjacent triangles calculate the same point.
+    let crossing = |i: usize, j: usize| {
+        let (i, j) = (i.min(j), i.max(j));
+        let (a, b) = (vertices[i], vertices[j]);
+        let t = fraction(z, (a.2, b.2));
+        ((i, j), (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t))
+    };
+
+    for &[a, b, c] in triangles {
+        let zs = [vertices[a].2, vertices[b].2, vertices[c].2];
+        if zs.iter().any(|v| v.is_nan()) {
+            continue;
+        }
+
+        let above = zs.map(|v| v > z);
+        let vs = [a, b, c];
+
+        // the vertex on its own side of the threshold, if any, and the other two.
+        let Some(lone) =
+            (0..3).find(|&i| above[i] != above[(i + 1) % 3] && above[i] != above[(i + 2) % 3])
+        else {
+            continue;
+        };
+        let (o1, o2) = (vs[(lone + 1) % 3], vs[(lone + 2) % 3]);
+        let lone = vs[lone];
+
+        let mut s = crossing(lone, o1);
+        let mut e = crossing(lone, o2);
+
+        // keep the vertices above the threshold on the left, that is on the negative side of the
+        // segment when the y axis points down.
+        let high = if vertices[lone].2 > z { lone } else { o1 };
+        if cross(s.1, e.1, (vertices[high].0, vertices[high].1)) > 0.0 {
+            std::mem::swap(&mut s, &mut e);
+        }
+
+        add_segment::<MeshEdge>(&mut segments, s, e);
+    }
+
+    build_contours(segments)
+}
+
+/// The z component of the cross product between `b - a` and `p - a`.
+fn cross(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nesting::signed_area;
+
+    type Tin = (Vec<(f64, f64, f64)>, Vec<[usize; 3]>);
+
+    /// A square split in four triangles around its center.
+    fn square(center_z: f64) -> Tin {
+        let vertices = vec![
+            (0.0, 0.0, 0.0),
+            (2.0, 0.0, 0.0),
+            (2.0, 2.0, 0.0),
+            (0.0, 2.0, 0.0),
+            (1.0, 1.0, center_z),
+        ];
+        let triangles = vec![[0, 1, 4], [1, 2, 4], [4, 2, 3], [3, 0, 4]];
+
+        (vertices, triangles)
+    }
+
+    #[test]
+    fn test_march_triangles_closed() {
+        let (vertices, triangles) = square(1.0);
+
+        let contours = march_triangles(&vertices, &triangles, 0.5);
+        assert_eq!(
+            contours,
+            vec![vec![
+                (0.5, 0.5),
+                (0.5, 1.5),
+                (1.5, 1.5),
+                (1.5, 0.5),
+                (0.5, 0.5)
+            ]]
+        );
+        assert_eq!(signed_area(&contours[0]), -1.0);
+
+        // the holes are wound the other way around
+        let contours = march_triangles(&vertices, &triangles, -0.5);
+        assert!(contours.is_empty());
+        let (vertices, _) = square(-1.0);
+        let contours = march_triangles(&vertices, &triangles, -0.5);
+        assert_eq!(signed_area(&contours[0]), 1.0);
+    }
+
+    #[test]
+    fn test_march_triangles_open() {
+        let vertices = vec![
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 1.0),
+            (1.0, 1.0, 1.0),
+            (0.0, 1.0, 0.0),
+        ];
+
+        // the winding of the triangles doesn't matter
+        for triangles in [[[0, 1, 2], [0, 2, 3]], [[0, 2, 1], [0, 3, 2]]] {
+            let contours = march_triangles(&vertices, &triangles, 0.5);
+            assert_eq!(contours, vec![vec![(0.5, 0.0), (0.5, 0.5), (0.5, 1.0)]]);
+        }
+    }
+
+    #[test]
+    fn test_march_triangles_nan() {
+        let (mut vertices, triangles) = square(1.0);
+        vertices[1].2 = f64::NAN;
+
+        let contours = march_triangles(&vertices, &triangles, 0.5);
+        assert_eq!(contours, vec![vec![(0.5, 0.5), (0.5, 1.5), (1.5, 1.5)]]);
+    }
+}
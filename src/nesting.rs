@@ -0,0 +1,209 @@
+//! Find how closed contours are nested inside each other, useful to turn them into polygons with
+//! holes.
+
+/// A polygon made of an exterior ring and zero or more holes.
+///
+/// All the rings are closed, that is their first and last points are the same. The exterior is
+/// wound so that the inside of the polygon lies on its left when the y axis points down, while
+/// holes are wound the other way around.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+/// A closed contour together with the closed contours directly inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ring {
+    /// The points of the ring, the first and last points are the same.
+    pub points: Vec<(f64, f64)>,
+
+    /// Whether the ring is a hole, that is it's wound clockwise when the y axis points down and
+    /// therefore the values above the threshold are outside of it.
+    pub is_hole: bool,
+
+    /// The rings directly contained in this one.
+    pub children: Vec<Ring>,
+}
+
+/// Build the containment tree of the closed contours in `contours`, open contours are ignored.
+///
+/// Each ring is a child of the smallest ring that contains it. Usually outer rings contain holes
+/// which in turn contain islands and so on, but when the region outside all the contours is above
+/// the threshold, as it happens with `Field::framed`, the roots are holes.
+pub fn nest(contours: &[Vec<(f64, f64)>]) -> Vec<Ring> {
+    let rings = contours
+        .iter()
+        .filter(|c| c.len() > 3 && c[0] == c[c.len() - 1])
+        .map(|c| (signed_area(c), c))
+        .filter(|(area, _)| *area != 0.0)
+        .collect::<Vec<_>>();
+
+    // a ring can only be contained in a bigger ring, thus by sorting the rings by their area the
+    // first one containing a given ring is also the smallest and therefore its parent.
+    let mut order = (0..rings.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| rings[i].0.abs().total_cmp(&rings[j].0.abs()));
+
+    let mut children = vec![vec![]; rings.len()];
+    let mut roots = vec![];
+    for (oi, &i) in order.iter().enumerate() {
+        let p = rings[i].1[0];
+        match order[oi + 1..].iter().find(|&&j| contains(rings[j].1, p)) {
+            Some(&parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    // keep the children in the same order they had in the input
+    roots.sort_unstable();
+    for c in &mut children {
+        c.sort_unstable();
+    }
+
+    fn build(i: usize, rings: &[(f64, &Vec<(f64, f64)>)], children: &[Vec<usize>]) -> Ring {
+        Ring {
+            points: rings[i].1.clone(),
+            is_hole: rings[i].0 > 0.0,
+            children: children[i]
+                .iter()
+                .map(|&c| build(c, rings, children))
+                .collect(),
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|i| build(i, &rings, &children))
+        .collect()
+}
+
+/// Turn the closed contours in `contours` into polygons with holes, islands inside holes become
+/// polygons on their own.
+///
+/// Holes that are not contained in any outer ring, see `nest`, are dropped.
+pub fn polygons(contours: &[Vec<(f64, f64)>]) -> Vec<Polygon> {
+    fn collect(ring: Ring, polygons: &mut Vec<Polygon>) {
+        if ring.is_hole {
+            for c in ring.children {
+                collect(c, polygons);
+            }
+            return;
+        }
+
+        let mut holes = vec![];
+        let mut nested = vec![];
+        for c in ring.children {
+            if c.is_hole {
+                holes.push(c.points);
+                nested.extend(c.children);
+            } else {
+                nested.push(c);
+            }
+        }
+
+        polygons.push(Polygon {
+            exterior: ring.points,
+            holes,
+        });
+
+        for r in nested {
+            collect(r, polygons);
+        }
+    }
+
+    let mut polygons = vec![];
+    for r in nest(contours) {
+        collect(r, &mut polygons);
+    }
+    polygons
+}
+
+/// Calculate the signed area of a closed ring using the shoelace formula. Rings whose inside is on
+/// their left when the y axis points down have a negative area.
+pub(crate) fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Check whether the point `p` is inside the given closed ring using the even-odd rule.
+fn contains(ring: &[(f64, f64)], p: (f64, f64)) -> bool {
+    let mut inside = false;
+
+    for w in ring.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{march, Field};
+
+    struct Nested;
+    impl Field for Nested {
+        fn dimensions(&self) -> (usize, usize) {
+            (11, 11)
+        }
+
+        fn z_at(&self, x: usize, y: usize) -> f64 {
+            match x.abs_diff(5).max(y.abs_diff(5)) {
+                0 | 1 | 4 => 1.0,
+                _ => 0.0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_nest() {
+        let contours = march(&Nested, 0.5);
+        assert_eq!(contours.len(), 3);
+
+        let mut rings = nest(&contours);
+        assert_eq!(rings.len(), 1);
+
+        let mut outer = rings.pop().unwrap();
+        assert!(!outer.is_hole);
+        assert_eq!(signed_area(&outer.points), -80.5);
+        assert_eq!(outer.children.len(), 1);
+
+        let mut hole = outer.children.pop().unwrap();
+        assert!(hole.is_hole);
+        assert_eq!(signed_area(&hole.points), 48.5);
+        assert_eq!(hole.children.len(), 1);
+
+        let island = hole.children.pop().unwrap();
+        assert!(!island.is_hole);
+        assert_eq!(signed_area(&island.points), -8.5);
+        assert!(island.children.is_empty());
+    }
+
+    #[test]
+    fn test_nest_framed() {
+        let contours = march(&Nested.framed(0.5), 0.5);
+
+        let rings = nest(&contours);
+        assert_eq!(rings.len(), 1);
+        assert!(rings[0].is_hole);
+        assert_eq!(rings[0].children.len(), 1);
+        assert!(!rings[0].children[0].is_hole);
+    }
+
+    #[test]
+    fn test_polygons() {
+        let polygons = polygons(&march(&Nested, 0.5));
+
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(signed_area(&polygons[0].exterior), -80.5);
+        assert_eq!(polygons[0].holes.len(), 1);
+        assert_eq!(signed_area(&polygons[0].holes[0]), 48.5);
+        assert_eq!(signed_area(&polygons[1].exterior), -8.5);
+        assert!(polygons[1].holes.is_empty());
+    }
+}
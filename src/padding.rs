@@ -0,0 +1,193 @@
+//! Grow a scalar field by one sample on each side, see `Field::padded`.
+
+use std::marker::PhantomData;
+
+use crate::transform::Transform;
+use crate::{Field, Scalar};
+
+/// How to fill the samples that a `Padded` field adds around the original field.
+///
+/// When the original field is empty there are no samples to repeat or mirror, thus the samples
+/// added by `Clamp` and `Mirror` are invalid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Padding {
+    /// Fill the new samples with the given value. When the value is on the other side of the
+    /// threshold with respect to the original samples on the boundary, every contour is closed.
+    Value(f64),
+
+    /// Repeat the closest sample of the original field.
+    Clamp,
+
+    /// Mirror the original field around the samples on its boundary.
+    Mirror,
+}
+
+/// A field that surrounds another field with a ring of samples, see `Field::padded`.
+///
+/// Unlike `Framed`, all the samples of the original field are preserved. The padded field has a
+/// sample more on each side than the original field, use `transform` to map the contours found
+/// on it back to the coordinates of the original field.
+#[derive(Debug, Clone)]
+pub struct Padded<'f, F, T = f64> {
+    field: &'f F,
+    padding: Padding,
+    _value: PhantomData<T>,
+}
+
+impl<'f, T: Scalar, F: Field<T>> Padded<'f, F, T> {
+    pub(crate) fn new(field: &'f F, padding: Padding) -> Self {
+        Padded {
+            field,
+            padding,
+            _value: PhantomData,
+        }
+    }
+
+    /// The transform that maps the coordinates of the padded field to the coordinates of the
+    /// original field.
+    pub fn transform(&self) -> Transform {
+        Transform::new((-1.0, -1.0), (1.0, 1.0))
+    }
+
+    /// The position of the sample of the original field the sample at the given position of the
+    /// padded field is taken from, `None` if it's filled with a constant value or if the original
+    /// field is empty.
+    fn original(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (w, h) = self.field.dimensions();
+        if w == 0 || h == 0 {
+            return None;
+        }
+
+        let inside = |v: usize, n: usize| v >= 1 && v <= n;
+
+        if inside(x, w) && inside(y, h) {
+            return Some((x - 1, y - 1));
+        }
+
+        let map = |v: usize, n: usize| {
+            let last = n.saturating_sub(1);
+            let i = match self.padding {
+                Padding::Mirror if v == 0 => 1,
+                Padding::Mirror if v > n => last.saturating_sub(1),
+                _ => v.saturating_sub(1),
+            };
+            i.min(last)
+        };
+
+        match self.padding {
+            Padding::Value(_) => None,
+            Padding::Clamp | Padding::Mirror => Some((map(x, w), map(y, h))),
+        }
+    }
+}
+
+impl<T: Scalar, F: Field<T>> Field for Padded<'_, F, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        let (w, h) = self.field.dimensions();
+        (w + 2, h + 2)
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        match (self.original(x, y), self.padding) {
+            (Some((x, y)), _) => self.field.z_at(x, y).to_f64(),
+            (None, Padding::Value(z)) => z,
+            (None, _) => f64::NAN,
+        }
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        match (self.original(x, y), self.padding) {
+            (Some((x, y)), _) => self.field.is_valid(x, y),
+            (None, Padding::Value(_)) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::{march, march_transformed};
+
+    fn grid() -> Grid<u8> {
+        #[rustfmt::skip]
+        let samples = vec![
+            1, 2, 0,
+            4, 5, 0,
+        ];
+
+        Grid::new(3, 2, samples)
+    }
+
+    #[test]
+    fn test_padded_value() {
+        let grid = grid();
+        let padded = grid.padded(Padding::Value(-1.0));
+
+        assert_eq!(padded.dimensions(), (5, 4));
+        assert_eq!(padded.z_at(0, 0), -1.0);
+        assert_eq!(padded.z_at(4, 3), -1.0);
+        assert_eq!(padded.z_at(1, 1), 1.0);
+        assert_eq!(padded.z_at(2, 2), 5.0);
+
+        // the samples on the boundary are preserved and the contour closes around them
+        let contours = march_transformed(&padded, 0.5, &padded.transform());
+        assert_eq!(contours.len(), 1);
+
+        let contour = &contours[0];
+        assert_eq!(contour[0], contour[contour.len() - 1]);
+        assert!(contour.contains(&(-0.25, 0.0)));
+        assert!(contour.contains(&(1.75, 0.0)));
+    }
+
+    #[test]
+    fn test_padded_clamp_and_mirror() {
+        let grid = grid();
+
+        let clamped = grid.padded(Padding::Clamp);
+        assert_eq!(clamped.z_at(0, 0), 1.0);
+        assert_eq!(clamped.z_at(0, 2), 4.0);
+        assert_eq!(clamped.z_at(4, 3), 0.0);
+        assert_eq!(clamped.z_at(2, 3), 5.0);
+
+        let mirrored = grid.padded(Padding::Mirror);
+        assert_eq!(mirrored.z_at(0, 0), 5.0);
+        assert_eq!(mirrored.z_at(0, 1), 2.0);
+        assert_eq!(mirrored.z_at(4, 1), 2.0);
+        assert_eq!(mirrored.z_at(1, 3), 1.0);
+
+        // clamping doesn't change the contours inside the original field
+        let contours = march_transformed(&clamped, 4.5, &clamped.transform());
+        assert!(contours.iter().flatten().any(|p| *p == (0.5, 1.0)));
+        assert!(march(&grid, 4.5).iter().flatten().any(|p| *p == (0.5, 1.0)));
+    }
+
+    #[test]
+    fn test_padded_single_sample() {
+        let grid = Grid::new(1, 1, vec![3.0]);
+
+        for padding in [Padding::Clamp, Padding::Mirror] {
+            let padded = grid.padded(padding);
+            assert_eq!(padded.dimensions(), (3, 3));
+            assert_eq!(padded.z_at(0, 0), 3.0);
+            assert_eq!(padded.z_at(2, 1), 3.0);
+        }
+    }
+
+    #[test]
+    fn test_padded_empty() {
+        let grid = Grid::<f64>::new(0, 0, vec![]);
+
+        for padding in [Padding::Clamp, Padding::Mirror] {
+            let padded = grid.padded(padding);
+            assert_eq!(padded.dimensions(), (2, 2));
+            assert!(!padded.is_valid(0, 0));
+            assert!(march(&padded, 0.5).is_empty());
+        }
+
+        let padded = grid.padded(Padding::Value(1.0));
+        assert_eq!(padded.z_at(1, 1), 1.0);
+        assert!(march(&padded, 0.5).is_empty());
+    }
+}
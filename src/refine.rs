@@ -0,0 +1,222 @@
+//! Find the points where the contours cross the edges of the cells more accurately than with
+//! linear interpolation, useful for fields that are strongly nonlinear between their samples.
+
+use std::collections::HashMap;
+
+use crate::{
+    build_contours, fraction, march_segments, Contours, Edge, Field, SaddleResolution, Scalar,
+};
+
+/// The iterative method used to find where a contour crosses an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RootFinder {
+    /// Halve the portion of the edge containing the crossing at each step. Slow, but it always
+    /// converges.
+    #[default]
+    Bisection,
+
+    /// Linearly interpolate between the ends of the portion of the edge containing the crossing,
+    /// using the Illinois variant to avoid getting stuck on one side.
+    RegulaFalsi,
+
+    /// Newton's method with a numerical derivative. It falls back to bisection whenever a step
+    /// would leave the portion of the edge containing the crossing.
+    Newton,
+}
+
+/// The maximum number of iterations of the root finders, after which the best estimate so far is
+/// used.
+const MAX_ITERATIONS: usize = 64;
+
+/// Find the contours of a given scalar field using `z` as the threshold value just like `march`,
+/// but find the points where the contours cross the edges of the cells with the given root
+/// finder, evaluating the field at fractional positions with `Field::sample`, until they're
+/// within `tolerance` from the actual crossing.
+///
+/// Since the default implementation of `Field::sample` interpolates linearly along the edges
+/// this is only useful for fields that override it, like `FnField`.
+pub fn march_refined<T: Scalar>(
+    field: &impl Field<T>,
+    z: f64,
+    root_finder: RootFinder,
+    tolerance: f64,
+) -> Contours {
+    let mut segments = march_segments(field, z, SaddleResolution::Fixed);
+
+    // each crossing is both the start of a segment and the end of another one, thus cache it so
+    // that it's refined only once.
+    let mut refined = HashMap::new();
+    let mut refine = |edge: Edge| {
+        *refined
+            .entry(edge)
+            .or_insert_with(|| refine_crossing(field, z, edge, root_finder, tolerance))
+    };
+
+    for (&sk, (s, ek, e)) in &mut segments {
+        *s = refine(sk);
+        *e = refine(*ek);
+    }
+
+    build_contours(segments)
+}
+
+/// Find where the contour crosses the given edge.
+fn refine_crossing<T: Scalar>(
+    field: &impl Field<T>,
+    z: f64,
+    edge: Edge,
+    root_finder: RootFinder,
+    tolerance: f64,
+) -> (f64, f64) {
+    let (x, y, dx, dy) = match edge {
+        Edge::Horizontal(x, y) => (x as f64, y as f64, 1.0, 0.0),
+        Edge::Vertical(x, y) => (x as f64, y as f64, 0.0, 1.0),
+    };
+
+    // the signed distance from the threshold along the edge, parametrized by t in [0, 1].
+    let g = |t: f64| field.sample(x + t * dx, y + t * dy) - z;
+
+    // the ends of the portion of the edge containing the crossing with their values, the
+    // crossing lies between a point above the threshold and one that is not.
+    let (mut t0, mut t1) = (0.0, 1.0);
+    let (mut g0, mut g1) = (g(t0), g(t1));
+
+    let mut t = fraction(0.0, (g0, g1));
+    let mut side = 0;
+
+    for i in 0..MAX_ITERATIONS {
+        if t1 - t0 <= tolerance {
+            break;
+        }
+
+        let next = match root_finder {
+            RootFinder::Bisection => (t0 + t1) / 2.0,
+            RootFinder::RegulaFalsi => t0 + fraction(0.0, (g0, g1)) * (t1 - t0),
+            RootFinder::Newton => {
+                let h = (tolerance / 2.0).min((t1 - t0) / 4.0);
+                let derivative = (g((t + h).min(1.0)) - g((t - h).max(0.0))) / (2.0 * h);
+                let next = t - g(t) / derivative;
+
+                if next > t0 && next < t1 {
+                    next
+                } else {
+                    (t0 + t1) / 2.0
+                }
+            }
+        };
+
+        let gn = g(next);
+        // the first step of regula falsi is the linear interpolation itself, thus don't stop there
+        let converged = gn == 0.0 || (i > 0 && (next - t).abs() <= tolerance);
+        t = next;
+
+        if (gn > 0.0) == (g0 > 0.0) {
+            t0 = next;
+            g0 = gn;
+
+            // Illinois: if the same end is moved twice in a row halve the value of the other one
+            if root_finder == RootFinder::RegulaFalsi && side == -1 {
+                g1 /= 2.0;
+            }
+            side = -1;
+        } else {
+            t1 = next;
+            g1 = gn;
+
+            if root_finder == RootFinder::RegulaFalsi && side == 1 {
+                g0 /= 2.0;
+            }
+            side = 1;
+        }
+
+        if converged && root_finder != RootFinder::Bisection {
+            break;
+        }
+    }
+
+    if root_finder == RootFinder::Bisection {
+        t = (t0 + t1) / 2.0;
+    }
+
+    (x + t * dx, y + t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::FnField;
+    use crate::grid::Grid;
+    use crate::march;
+
+    fn circle() -> FnField<impl Fn(f64, f64) -> f64> {
+        // strongly nonlinear along the edges of the cells
+        FnField::new(
+            |x: f64, y: f64| (x * x + y * y).powi(3),
+            (-2.0, -2.0),
+            (4.0, 4.0),
+            (9, 9),
+        )
+    }
+
+    fn max_error(contours: &Contours) -> f64 {
+        let transform = circle().transform();
+        transform
+            .apply_contours(contours.clone())
+            .iter()
+            .flatten()
+            .map(|&(x, y)| (x.hypot(y) - 1.0).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_march_refined() {
+        let coarse = march(&circle(), 1.0);
+        assert!(max_error(&coarse) > 0.1);
+
+        for root_finder in [
+            RootFinder::Bisection,
+            RootFinder::RegulaFalsi,
+            RootFinder::Newton,
+        ] {
+            let refined = march_refined(&circle(), 1.0, root_finder, 1e-9);
+
+            assert_eq!(refined.len(), coarse.len());
+            assert_eq!(refined[0].len(), coarse[0].len());
+            assert!(max_error(&refined) < 1e-8, "{:?}", root_finder);
+        }
+    }
+
+    #[test]
+    fn test_march_refined_discrete_field() {
+        // the default sample interpolates linearly along the edges, thus nothing changes
+        let grid = Grid::new(3, 3, vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let refined = march_refined(&grid, 0.3, RootFinder::RegulaFalsi, 1e-12);
+        let expected = march(&grid, 0.3);
+        assert_eq!(refined.len(), expected.len());
+        for (r, e) in refined[0].iter().zip(&expected[0]) {
+            assert!((r.0 - e.0).abs() < 1e-9 && (r.1 - e.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_march_refined_invalid_row() {
+        // the crossings on the edges next to the NaN row only depend on the samples of the edge
+        let nan = f64::NAN;
+        let grid = Grid::new(3, 3, vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0, nan, nan, nan]);
+
+        for root_finder in [
+            RootFinder::Bisection,
+            RootFinder::RegulaFalsi,
+            RootFinder::Newton,
+        ] {
+            let refined = march_refined(&grid, 0.5, root_finder, 1e-12);
+            let expected = march(&grid, 0.5);
+            assert_eq!(refined.len(), expected.len());
+
+            for (r, e) in refined.iter().flatten().zip(expected.iter().flatten()) {
+                assert!((r.0 - e.0).abs() < 1e-9 && (r.1 - e.1).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Present a scalar field at a different resolution by interpolating its samples, useful to get
+//! smoother contours out of low resolution rasters.
+
+use std::marker::PhantomData;
+
+use crate::transform::Transform;
+use crate::{Field, Scalar};
+
+/// How to calculate the values between the samples of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Take the value of the closest sample.
+    Nearest,
+
+    /// Use `Field::sample`, which by default interpolates bilinearly between the four closest
+    /// samples, but that can be more accurate for fields that can be evaluated anywhere.
+    #[default]
+    Bilinear,
+
+    /// Interpolate between the sixteen closest samples with Catmull-Rom splines. It's smoother
+    /// than bilinear interpolation, but it can overshoot the values of the samples.
+    Bicubic,
+}
+
+/// A field that presents another field at a different resolution, see `Field::resample`.
+///
+/// The samples on the boundary of the resampled field lie on the boundary of the original field,
+/// thus the contours found on it can be mapped back to the original field with `transform`.
+#[derive(Debug, Clone)]
+pub struct Resampled<'f, F, T = f64> {
+    field: &'f F,
+    dimensions: (usize, usize),
+    interpolation: Interpolation,
+    _value: PhantomData<T>,
+}
+
+impl<'f, T: Scalar, F: Field<T>> Resampled<'f, F, T> {
+    pub(crate) fn new(
+        field: &'f F,
+        dimensions: (usize, usize),
+        interpolation: Interpolation,
+    ) -> Self {
+        Resampled {
+            field,
+            dimensions,
+            interpolation,
+            _value: PhantomData,
+        }
+    }
+
+    /// The transform that maps the coordinates of the resampled field to the coordinates of the
+    /// original field.
+    pub fn transform(&self) -> Transform {
+        let (w, h) = self.dimensions;
+        let (fw, fh) = self.field.dimensions();
+        let scale = |n: usize, original: usize| {
+            original.saturating_sub(1) as f64 / n.saturating_sub(1).max(1) as f64
+        };
+
+        Transform::new((0.0, 0.0), (scale(w, fw), scale(h, fh)))
+    }
+
+    /// The samples of the original field the value at the given position is calculated from.
+    fn neighborhood(&self, x: f64, y: f64) -> (Vec<usize>, Vec<usize>) {
+        let (fw, fh) = self.field.dimensions();
+        let around = |v: f64, n: usize| {
+            let last = n.saturating_sub(1) as isize;
+            let clamp = |i: isize| i.clamp(0, last) as usize;
+            let i = v.floor() as isize;
+
+            match self.interpolation {
+                Interpolation::Nearest => vec![clamp(v.round() as isize)],
+                Interpolation::Bilinear => vec![clamp(i), clamp(i + 1)],
+                Interpolation::Bicubic => (i - 1..=i + 2).map(clamp).collect(),
+            }
+        };
+
+        (around(x, fw), around(y, fh))
+    }
+}
+
+impl<T: Scalar, F: Field<T>> Field for Resampled<'_, F, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        let (x, y) = self.transform().apply((x as f64, y as f64));
+
+        match self.interpolation {
+            Interpolation::Nearest => {
+                let (xs, ys) = self.neighborhood(x, y);
+                self.field.z_at(xs[0], ys[0]).to_f64()
+            }
+            Interpolation::Bilinear => self.field.sample(x, y),
+            Interpolation::Bicubic => {
+                let (xs, ys) = self.neighborhood(x, y);
+                let (tx, ty) = (x - x.floor(), y - y.floor());
+
+                let row =
+                    |y| catmull_rom([0, 1, 2, 3].map(|i| self.field.z_at(xs[i], y).to_f64()), tx);
+                catmull_rom([0, 1, 2, 3].map(|i| row(ys[i])), ty)
+            }
+        }
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        let (x, y) = self.transform().apply((x as f64, y as f64));
+        let (xs, ys) = self.neighborhood(x, y);
+
+        ys.iter()
+            .all(|&y| xs.iter().all(|&x| self.field.is_valid(x, y)))
+    }
+}
+
+/// Interpolate between `p1`, when `t` is 0, and `p2`, when `t` is 1, with a Catmull-Rom spline.
+fn catmull_rom([p0, p1, p2, p3]: [f64; 4], t: f64) -> f64 {
+    let a = -0.5 * p0 + 1.5 * p1 - 1.5 * p2 + 0.5 * p3;
+    let b = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+    let c = -0.5 * p0 + 0.5 * p2;
+
+    ((a * t + b) * t + c) * t + p1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::march;
+
+    fn grid() -> Grid<u8> {
+        #[rustfmt::skip]
+        let samples = vec![
+            0, 0, 0, 0,
+            0, 4, 8, 0,
+            0, 4, 8, 0,
+            0, 0, 0, 0,
+        ];
+
+        Grid::new(4, 4, samples)
+    }
+
+    #[test]
+    fn test_resampled() {
+        let grid = grid();
+
+        for interpolation in [
+            Interpolation::Nearest,
+            Interpolation::Bilinear,
+            Interpolation::Bicubic,
+        ] {
+            let resampled = grid.resample(7, 7, interpolation);
+            assert_eq!(resampled.dimensions(), (7, 7));
+            assert_eq!(resampled.transform().apply((6.0, 6.0)), (3.0, 3.0));
+
+            // the original samples are preserved
+            for y in 0..4 {
+                for x in 0..4 {
+                    assert_eq!(resampled.z_at(x * 2, y * 2), f64::from(grid.z_at(x, y)));
+                }
+            }
+
+            let contours = march(&resampled, 2.0);
+            assert_eq!(contours.len(), 1);
+        }
+
+        let nearest = grid.resample(7, 7, Interpolation::Nearest);
+        assert_eq!(nearest.z_at(3, 2), 8.0);
+
+        let bilinear = grid.resample(7, 7, Interpolation::Bilinear);
+        assert_eq!(bilinear.z_at(3, 2), 6.0);
+        assert_eq!(bilinear.z_at(3, 3), 6.0);
+
+        let bicubic = grid.resample(7, 7, Interpolation::Bicubic);
+        assert_eq!(bicubic.z_at(3, 2), 6.75);
+    }
+
+    #[test]
+    fn test_catmull_rom() {
+        assert_eq!(catmull_rom([0.0, 1.0, 2.0, 3.0], 0.5), 1.5);
+        assert_eq!(catmull_rom([0.0, 1.0, 2.0, 3.0], 0.0), 1.0);
+        assert_eq!(catmull_rom([0.0, 1.0, 2.0, 3.0], 1.0), 2.0);
+    }
+}
@@ -0,0 +1,144 @@
+//! Find the contours of horizontal slices of 3D scalar fields, like the outlines of each layer
+//! of a 3D print.
+
+use std::marker::PhantomData;
+
+use crate::marching_cubes::Field3;
+use crate::{march, Contours, Field, Scalar};
+
+/// A 2D scalar field made of the values of a 3D scalar field at a given height, that is z
+/// coordinate. The values between two layers of samples are linearly interpolated.
+#[derive(Debug, Clone)]
+pub struct Slice<'v, V, T = f64> {
+    volume: &'v V,
+    layer: usize,
+
+    /// How far the slice is from `layer` towards the next layer, in `[0, 1)`.
+    t: f64,
+
+    _value: PhantomData<T>,
+}
+
+impl<'v, T: Scalar, V: Field3<T>> Slice<'v, V, T> {
+    /// Create the slice of the given volume made of the samples whose z coordinate is `z`.
+    ///
+    /// Panics if `z` is outside the volume.
+    pub fn at_index(volume: &'v V, z: usize) -> Self {
+        Self::at_height(volume, z as f64)
+    }
+
+    /// Create the slice of the given volume at the given height, interpolating between the two
+    /// closest layers of samples.
+    ///
+    /// Panics if `height` is outside the volume.
+    pub fn at_height(volume: &'v V, height: f64) -> Self {
+        let (_, _, depth) = volume.dimensions();
+        assert!(
+            height >= 0.0 && height <= depth.saturating_sub(1) as f64,
+            "height {} is outside the volume",
+            height
+        );
+
+        let layer = height.floor() as usize;
+        Slice {
+            volume,
+            layer,
+            t: height - layer as f64,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T: Scalar, V: Field3<T>> Field for Slice<'_, V, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        let (w, h, _) = self.volume.dimensions();
+        (w, h)
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> f64 {
+        let z0 = self.volume.value_at(x, y, self.layer).to_f64();
+        if self.t == 0.0 {
+            return z0;
+        }
+
+        let z1 = self.volume.value_at(x, y, self.layer + 1).to_f64();
+        z0 + (z1 - z0) * self.t
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        self.volume.is_valid(x, y, self.layer)
+            && (self.t == 0.0 || self.volume.is_valid(x, y, self.layer + 1))
+    }
+}
+
+/// Find the contours of the slices of the given volume at each of the given heights using `level`
+/// as the threshold value. The contours are returned in the same order as `heights`.
+///
+/// Panics if a height is outside the volume.
+pub fn march_slices<T: Scalar>(
+    volume: &impl Field3<T>,
+    level: f64,
+    heights: impl IntoIterator<Item = f64>,
+) -> Vec<(f64, Contours)> {
+    heights
+        .into_iter()
+        .map(|h| (h, march(&Slice::at_height(volume, h), level)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    struct Ball;
+    impl Field3 for Ball {
+        fn dimensions(&self) -> (usize, usize, usize) {
+            (11, 11, 11)
+        }
+
+        fn value_at(&self, x: usize, y: usize, z: usize) -> f64 {
+            let (x, y, z) = (x as f64 - 5.0, y as f64 - 5.0, z as f64 - 5.0);
+            4.0 - (x * x + y * y + z * z).sqrt()
+        }
+    }
+
+    #[test]
+    fn test_slice_at_index() {
+        let slice = Slice::at_index(&Ball, 3);
+        assert_eq!(slice.dimensions(), (11, 11));
+
+        let samples = (0..11)
+            .flat_map(|y| (0..11).map(move |x| Ball.value_at(x, y, 3)))
+            .collect();
+        let grid = Grid::new(11, 11, samples);
+
+        assert_eq!(march(&slice, 0.0), march(&grid, 0.0));
+    }
+
+    #[test]
+    fn test_slice_at_height() {
+        let slice = Slice::at_height(&Ball, 4.25);
+        let expected = 0.75 * Ball.value_at(2, 7, 4) + 0.25 * Ball.value_at(2, 7, 5);
+        assert!((slice.z_at(2, 7) - expected).abs() < 1e-12);
+
+        let top = Slice::at_height(&Ball, 10.0);
+        assert_eq!(top.z_at(5, 5), Ball.value_at(5, 5, 10));
+    }
+
+    #[test]
+    fn test_march_slices() {
+        let slices = march_slices(&Ball, 0.0, [0.0, 2.5, 5.0, 10.0]);
+
+        let counts = slices.iter().map(|(_, c)| c.len()).collect::<Vec<_>>();
+        assert_eq!(counts, vec![0, 1, 1, 0]);
+        assert_eq!(slices[1].0, 2.5);
+        assert_eq!(slices[2].1, march(&Slice::at_index(&Ball, 5), 0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_outside() {
+        Slice::at_height(&Ball, 10.5);
+    }
+}
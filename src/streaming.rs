@@ -0,0 +1,272 @@
+//! Find the contours of fields that are too big to fit in memory by reading them one row at a
+//! time.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    march_cell, normalize_paths, Contours, Crossing, Edge, Path, SaddleResolution, Scalar,
+};
+
+/// A `Marcher` finds the contours of a scalar field whose rows are pushed one at a time.
+///
+/// Only the previous row and the contours that are still open are kept in memory, while the
+/// contours are returned as soon as they're complete. Just like `march`, the samples whose value
+/// is NaN are considered invalid and the cells touching them are skipped.
+#[derive(Debug, Clone)]
+pub struct Marcher {
+    z: f64,
+    saddle: SaddleResolution,
+    width: usize,
+
+    /// The number of rows pushed so far.
+    rows: usize,
+    prev_row: Vec<f64>,
+
+    fragments: HashMap<usize, Fragment>,
+    next_fragment_id: usize,
+
+    /// The fragments indexed by the edge their first point lies on.
+    heads: HashMap<Edge, usize>,
+
+    /// The fragments indexed by the edge their last point lies on.
+    tails: HashMap<Edge, usize>,
+}
+
+/// A part of a contour that is still being built.
+#[derive(Debug, Clone)]
+struct Fragment {
+    points: VecDeque<(f64, f64)>,
+    head: Edge,
+    tail: Edge,
+}
+
+impl Marcher {
+    /// Create a `Marcher` that finds the contours of a field whose rows have `width` samples
+    /// using `z` as the threshold value.
+    pub fn new(width: usize, z: f64) -> Self {
+        Marcher {
+            z,
+            saddle: SaddleResolution::Fixed,
+            width,
+            rows: 0,
+            prev_row: Vec::with_capacity(width),
+            fragments: HashMap::new(),
+            next_fragment_id: 0,
+            heads: HashMap::new(),
+            tails: HashMap::new(),
+        }
+    }
+
+    /// Resolve the ambiguous saddle cells with the given strategy.
+    pub fn with_saddle_resolution(mut self, saddle: SaddleResolution) -> Self {
+        self.saddle = saddle;
+        self
+    }
+
+    /// Push the next row of the field and return the contours that have been completed by it.
+    /// Like `Field`, the samples can be of any `Scalar` type and they're converted to `f64`.
+    ///
+    /// Panics if the row doesn't have exactly `width` samples.
+    pub fn push_row<T: Scalar>(&mut self, row: &[T]) -> Contours {
+        assert_eq!(row.len(), self.width, "row has the wrong number of samples");
+
+        let mut paths = vec![];
+
+        if self.rows > 0 {
+            let y = self.rows - 1;
+            let prev_row = std::mem::take(&mut self.prev_row);
+
+            for x in 0..self.width.saturating_sub(1) {
+                let zs = [
+                    prev_row[x],
+                    prev_row[x + 1],
+                    row[x + 1].to_f64(),
+                    row[x].to_f64(),
+                ];
+                march_cell((x, y), zs, self.z, self.saddle, &mut |s, e| {
+                    if let Some(p) = self.add_segment(s, e) {
+                        paths.push(p);
+                    }
+                });
+            }
+
+            self.prev_row = prev_row;
+
+            // the only fragments that can still grow are the ones that end on the bottom edges of
+            // this row, all the others are complete open paths.
+            let on_frontier = |e| matches!(e, Edge::Horizontal(_, ey) if ey == y + 1);
+            let complete = self
+                .fragments
+                .iter()
+                .filter(|(_, f)| !on_frontier(f.head) && !on_frontier(f.tail))
+                .map(|(&id, _)| id)
+                .collect::<Vec<_>>();
+
+            for id in complete {
+                paths.push(self.remove_fragment(id));
+            }
+        }
+
+        self.prev_row.clear();
+        self.prev_row.extend(row.iter().map(|z| z.to_f64()));
+        self.rows += 1;
+
+        into_contours(paths)
+    }
+
+    /// Return all the contours that are still open, to be called once all the rows have been
+    /// pushed.
+    pub fn finish(mut self) -> Contours {
+        let ids = self.fragments.keys().copied().collect::<Vec<_>>();
+
+        let paths = ids
+            .into_iter()
+            .map(|id| self.remove_fragment(id))
+            .collect::<Vec<_>>();
+
+        into_contours(paths)
+    }
+
+    /// Add the given segment to the open fragments and return the contour it closes, if any.
+    fn add_segment(&mut self, (sk, s): Crossing, (ek, e): Crossing) -> Option<Path> {
+        let before = self.tails.remove(&sk);
+        let after = self.heads.remove(&ek);
+
+        match (before, after) {
+            (Some(b), Some(a)) if a == b => {
+                let mut f = self.fragments.remove(&a).unwrap();
+                let first = f.points[0];
+                f.points.push_back(first);
+                Some(Path {
+                    points: f.points.into(),
+                    ends: (f.head, f.head),
+                })
+            }
+            (Some(b), Some(a)) => {
+                let after = self.fragments.remove(&a).unwrap();
+                let before = self.fragments.get_mut(&b).unwrap();
+                before.points.extend(after.points);
+                before.tail = after.tail;
+                self.tails.insert(after.tail, b);
+                None
+            }
+            (Some(b), None) => {
+                let before = self.fragments.get_mut(&b).unwrap();
+                before.points.push_back(e);
+                before.tail = ek;
+                self.tails.insert(ek, b);
+                None
+            }
+            (None, Some(a)) => {
+                let after = self.fragments.get_mut(&a).unwrap();
+                after.points.push_front(s);
+                after.head = sk;
+                self.heads.insert(sk, a);
+                None
+            }
+            (None, None) => {
+                let id = self.next_fragment_id;
+                self.next_fragment_id += 1;
+
+                self.fragments.insert(
+                    id,
+                    Fragment {
+                        points: VecDeque::from([s, e]),
+                        head: sk,
+                        tail: ek,
+                    },
+                );
+                self.heads.insert(sk, id);
+                self.tails.insert(ek, id);
+                None
+            }
+        }
+    }
+
+    fn remove_fragment(&mut self, id: usize) -> Path {
+        let f = self.fragments.remove(&id).unwrap();
+        self.heads.remove(&f.head);
+        self.tails.remove(&f.tail);
+        Path {
+            points: f.points.into(),
+            ends: (f.head, f.tail),
+        }
+    }
+}
+
+/// Normalize the given paths just like `march` does and return their points.
+fn into_contours(mut paths: Vec<Path>) -> Contours {
+    normalize_paths(&mut paths);
+    paths.into_iter().map(|p| p.points).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{integer_noise, Waves};
+    use crate::{compare_points, march, Field};
+
+    #[test]
+    fn test_marcher_same_as_march() {
+        let waves = Waves(57, 91);
+        let (w, h) = waves.dimensions();
+
+        for z in [-1.0, -0.3, 0.0, 0.5, 1.2, 10.0] {
+            let mut marcher = Marcher::new(w, z);
+
+            let mut contours = vec![];
+            for y in 0..h {
+                let row = (0..w).map(|x| waves.z_at(x, y)).collect::<Vec<_>>();
+                contours.extend(marcher.push_row(&row));
+            }
+            contours.extend(marcher.finish());
+            contours.sort_by(|a, b| compare_points(a, b));
+
+            assert_eq!(contours, march(&waves, z));
+        }
+    }
+
+    #[test]
+    fn test_marcher_same_as_march_with_samples_at_threshold() {
+        for seed in 0..20 {
+            let grid = integer_noise(25, 30, seed);
+            let (w, h) = grid.dimensions();
+
+            let mut marcher = Marcher::new(w, 2.0);
+
+            let mut contours = vec![];
+            for y in 0..h {
+                let row = (0..w).map(|x| grid.z_at(x, y)).collect::<Vec<_>>();
+                contours.extend(marcher.push_row(&row));
+            }
+            contours.extend(marcher.finish());
+            contours.sort_by(|a, b| compare_points(a, b));
+
+            assert_eq!(contours, march(&grid, 2.0));
+        }
+    }
+
+    #[test]
+    fn test_marcher_emits_closed_contours_early() {
+        let mut marcher = Marcher::new(4, 0.5);
+
+        assert!(marcher.push_row(&[0.0, 0.0, 0.0, 0.0]).is_empty());
+        assert!(marcher.push_row(&[0.0, 1.0, 0.0, 0.0]).is_empty());
+
+        let contours = marcher.push_row(&[0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(
+            contours,
+            vec![vec![
+                (1.0, 0.5),
+                (0.5, 1.0),
+                (1.0, 1.5),
+                (1.5, 1.0),
+                (1.0, 0.5)
+            ]]
+        );
+        assert!(marcher.fragments.is_empty());
+
+        assert!(marcher.push_row(&[0.0, 0.0, 0.0, 1.0]).is_empty());
+        assert_eq!(marcher.finish(), vec![vec![(3.0, 2.5), (2.5, 3.0)]]);
+    }
+}
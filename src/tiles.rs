@@ -0,0 +1,176 @@
+//! March a field one tile at a time and merge the contours of the tiles back together.
+//!
+//! Adjacent tiles overlap by one sample: a tile made of `w` by `h` cells reads `w + 1` by `h + 1`
+//! samples and the tile on its right starts on its last column of samples. Since the samples on
+//! the seams are shared, the contours crossing a seam end in one tile and start in the other on
+//! exactly the same edge of the grid.
+
+use crate::{
+    add_segment, build_paths, for_each_cell_in, march_cell, normalize_paths, Contours, Edge, Field,
+    Path, SaddleResolution, Scalar, SegmentsMap,
+};
+
+/// The contours of a tile of a field, see `march_tile`.
+///
+/// Besides the points, a tile keeps track of the grid edges the open contours start and end on,
+/// so that `merge_tiles` can join them exactly even when several contours meet at the same point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    paths: Vec<Path>,
+}
+
+impl Tile {
+    /// The contours of the tile, in the same order as `march` would return them.
+    pub fn contours(&self) -> Contours {
+        self.paths.iter().map(|p| p.points.clone()).collect()
+    }
+}
+
+/// The points of the contours being merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    /// The point at the given index of the given contour.
+    Inner(usize, usize),
+
+    /// The first or last point of a contour, lying on the given edge.
+    Endpoint(Edge),
+}
+
+/// Find the contours of the tile of `field` whose upper left corner is at `(x, y)` and that is
+/// made of `width` by `height` cells using `z` as the threshold value.
+///
+/// The tile is clipped to the field and the contours are in the coordinates of the whole field,
+/// so that the contours of adjacent tiles can be joined together with `merge_tiles`.
+pub fn march_tile<T: Scalar>(
+    field: &impl Field<T>,
+    z: f64,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+) -> Tile {
+    let (field_width, field_height) = field.dimensions();
+    let cols = x..(x + width).min(field_width.saturating_sub(1));
+    let rows = y..(y + height).min(field_height.saturating_sub(1));
+
+    let mut segments = SegmentsMap::new();
+    for_each_cell_in(field, (cols, rows), |xy, zs| {
+        march_cell(xy, zs, z, SaddleResolution::Fixed, &mut |s, e| {
+            add_segment(&mut segments, s, e)
+        });
+    });
+
+    Tile {
+        paths: build_paths(segments),
+    }
+}
+
+/// Merge the contours of adjacent tiles, as returned by `march_tile`, joining the open contours
+/// that continue across the seams.
+///
+/// When the tiles cover the whole field the result is the same as the one of `march`.
+pub fn merge_tiles(tiles: impl IntoIterator<Item = Tile>) -> Contours {
+    let mut closed = vec![];
+    let mut segments = SegmentsMap::new();
+
+    for (pi, path) in tiles.into_iter().flat_map(|t| t.paths).enumerate() {
+        let (first, last) = path.ends;
+
+        if path.is_closed() {
+            closed.push(Path {
+                points: path.points,
+                ends: (Node::Endpoint(first), Node::Endpoint(last)),
+            });
+            continue;
+        }
+
+        // the open contours that continue across a seam end and start on the same edge, thus
+        // their endpoints share the same key and build_paths joins them.
+        let n = path.points.len() - 1;
+        let key = |i| match i {
+            0 => Node::Endpoint(first),
+            i if i == n => Node::Endpoint(last),
+            i => Node::Inner(pi, i),
+        };
+
+        for (i, w) in path.points.windows(2).enumerate() {
+            add_segment(&mut segments, (key(i), w[0]), (key(i + 1), w[1]));
+        }
+    }
+
+    let mut paths = build_paths(segments);
+    paths.extend(closed);
+    normalize_paths(&mut paths);
+    paths.into_iter().map(|p| p.points).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::march;
+    use crate::tests::{integer_noise, Waves};
+
+    #[test]
+    fn test_merge_tiles_same_as_march() {
+        let waves = Waves(67, 45);
+        let (w, h) = waves.dimensions();
+
+        for tile_size in [(1, 1), (7, 5), (16, 16), (100, 100)] {
+            for z in [-1.0, -0.2, 0.0, 0.5, 1.3, 10.0] {
+                let tiles = (0..h - 1).step_by(tile_size.1).flat_map(|y| {
+                    (0..w - 1)
+                        .step_by(tile_size.0)
+                        .map(move |x| march_tile(&waves, z, (x, y), tile_size))
+                });
+
+                assert_eq!(merge_tiles(tiles), march(&waves, z));
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_tiles_with_samples_at_threshold() {
+        for seed in 0..50 {
+            let grid = integer_noise(9, 7, seed);
+            let (w, h) = grid.dimensions();
+
+            let tiles = (0..h - 1).step_by(2).flat_map(|y| {
+                (0..w - 1)
+                    .step_by(2)
+                    .map(|x| march_tile(&grid, 2.0, (x, y), (2, 2)))
+                    .collect::<Vec<_>>()
+            });
+
+            assert_eq!(merge_tiles(tiles), march(&grid, 2.0));
+        }
+    }
+
+    #[test]
+    fn test_march_tile() {
+        struct Bump;
+        impl Field for Bump {
+            fn dimensions(&self) -> (usize, usize) {
+                (5, 5)
+            }
+
+            fn z_at(&self, x: usize, y: usize) -> f64 {
+                if (x, y) == (2, 2) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+
+        let left = march_tile(&Bump, 0.5, (0, 0), (2, 4));
+        let right = march_tile(&Bump, 0.5, (2, 0), (2, 4));
+        assert_eq!(
+            left.contours(),
+            vec![vec![(2.0, 1.5), (1.5, 2.0), (2.0, 2.5)]]
+        );
+        assert_eq!(
+            right.contours(),
+            vec![vec![(2.0, 2.5), (2.5, 2.0), (2.0, 1.5)]]
+        );
+
+        assert_eq!(merge_tiles([left, right]), march(&Bump, 0.5));
+    }
+}
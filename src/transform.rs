@@ -0,0 +1,146 @@
+//! Affine transforms to map contours from grid coordinates to world coordinates, like the
+//! projected coordinates of a georeferenced raster.
+
+use crate::Contours;
+
+/// An affine transform that maps the point `(x, y)` of the grid to
+/// `origin + x * x_axis + y * y_axis`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    /// The world coordinates of the sample at `(0, 0)`.
+    pub origin: (f64, f64),
+
+    /// How much the world coordinates change when moving by one sample along the x axis of the
+    /// grid.
+    pub x_axis: (f64, f64),
+
+    /// How much the world coordinates change when moving by one sample along the y axis of the
+    /// grid.
+    pub y_axis: (f64, f64),
+}
+
+impl Transform {
+    /// The transform that leaves the points unchanged.
+    pub fn identity() -> Self {
+        Transform::new((0.0, 0.0), (1.0, 1.0))
+    }
+
+    /// Create a transform that maps the sample at `(0, 0)` to `origin` and whose samples are
+    /// `pixel_size` apart.
+    pub fn new(origin: (f64, f64), pixel_size: (f64, f64)) -> Self {
+        Transform {
+            origin,
+            x_axis: (pixel_size.0, 0.0),
+            y_axis: (0.0, pixel_size.1),
+        }
+    }
+
+    /// Same as `new`, but flip the y axis so that it points up like in north up rasters, where
+    /// the first row is the northernmost one.
+    pub fn north_up(origin: (f64, f64), pixel_size: (f64, f64)) -> Self {
+        Transform::new(origin, (pixel_size.0, -pixel_size.1))
+    }
+
+    /// Create a transform from a GDAL geotransform, that is the six coefficients such that the
+    /// world coordinates of the pixel `(x, y)` are `(gt[0] + x * gt[1] + y * gt[2], gt[3] + x *
+    /// gt[4] + y * gt[5])`.
+    ///
+    /// GDAL geotransforms refer to the top left corner of the pixels, while the samples of a
+    /// field lie at their center, thus the transform is shifted by half a pixel.
+    pub fn from_gdal(gt: [f64; 6]) -> Self {
+        let x_axis = (gt[1], gt[4]);
+        let y_axis = (gt[2], gt[5]);
+
+        Transform {
+            origin: (
+                gt[0] + (x_axis.0 + y_axis.0) / 2.0,
+                gt[3] + (x_axis.1 + y_axis.1) / 2.0,
+            ),
+            x_axis,
+            y_axis,
+        }
+    }
+
+    /// Rotate the axes of the grid by `angle` radians around the origin.
+    pub fn with_rotation(self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |(x, y): (f64, f64)| (x * cos - y * sin, x * sin + y * cos);
+
+        Transform {
+            x_axis: rotate(self.x_axis),
+            y_axis: rotate(self.y_axis),
+            ..self
+        }
+    }
+
+    /// Create the transform that applies this transform and then `next`, useful when the world
+    /// coordinates of this transform are the grid coordinates of another one.
+    pub fn then(&self, next: &Transform) -> Self {
+        let linear = |(x, y): (f64, f64)| {
+            (
+                x * next.x_axis.0 + y * next.y_axis.0,
+                x * next.x_axis.1 + y * next.y_axis.1,
+            )
+        };
+
+        Transform {
+            origin: next.apply(self.origin),
+            x_axis: linear(self.x_axis),
+            y_axis: linear(self.y_axis),
+        }
+    }
+
+    /// Map the given point from grid coordinates to world coordinates.
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.origin.0 + x * self.x_axis.0 + y * self.y_axis.0,
+            self.origin.1 + x * self.x_axis.1 + y * self.y_axis.1,
+        )
+    }
+
+    /// Map all the points of the given contours from grid coordinates to world coordinates. The
+    /// order of the contours and of their points is left unchanged, thus closed contours stay
+    /// closed exactly.
+    pub fn apply_contours(&self, mut contours: Contours) -> Contours {
+        for p in contours.iter_mut().flatten() {
+            *p = self.apply(*p);
+        }
+        contours
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform() {
+        let t = Transform::north_up((100.0, 50.0), (2.0, 3.0));
+        assert_eq!(t.apply((0.0, 0.0)), (100.0, 50.0));
+        assert_eq!(t.apply((1.5, 2.0)), (103.0, 44.0));
+
+        let gt = Transform::from_gdal([100.0, 2.0, 0.0, 50.0, 0.0, -3.0]);
+        assert_eq!(gt.apply((0.0, 0.0)), (101.0, 48.5));
+        assert_eq!(gt.apply((1.0, 1.0)), (103.0, 45.5));
+
+        let r = Transform::new((1.0, 1.0), (2.0, 2.0)).with_rotation(std::f64::consts::FRAC_PI_2);
+        let (x, y) = r.apply((1.0, 0.0));
+        assert!((x - 1.0).abs() < 1e-12 && (y - 3.0).abs() < 1e-12);
+
+        let contours = vec![vec![(0.0, 0.0), (1.0, 0.5), (0.0, 0.0)]];
+        assert_eq!(
+            Transform::identity().apply_contours(contours.clone()),
+            contours
+        );
+        assert_eq!(
+            t.apply_contours(contours),
+            vec![vec![(100.0, 50.0), (102.0, 48.5), (100.0, 50.0)]]
+        );
+    }
+}
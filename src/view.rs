@@ -0,0 +1,221 @@
+//! Lazy views over a portion or a reordering of the samples of a scalar field, see
+//! `Field::crop` and the other views of `Field`.
+
+use std::marker::PhantomData;
+
+use crate::transform::Transform;
+use crate::{Field, Scalar};
+
+/// A view over the samples of another field, the parent field, see `Field::crop`,
+/// `Field::step_by`, `Field::transpose`, `Field::flip_x` and `Field::flip_y`.
+///
+/// The contours found on a view are in the coordinates of the view, use `transform` to map them
+/// back to the coordinates of the parent field. Note that transposing or flipping a field mirrors
+/// it, thus the contours mapped back to the parent field are wound the other way around.
+#[derive(Debug, Clone)]
+pub struct View<'f, F, T = f64> {
+    field: &'f F,
+    dimensions: (usize, usize),
+
+    /// The sample of the parent field at the origin of the view.
+    origin: (usize, usize),
+
+    /// How much the coordinates of the parent field change when moving by one sample along the
+    /// x and y axes of the view.
+    x_axis: (isize, isize),
+    y_axis: (isize, isize),
+
+    _value: PhantomData<T>,
+}
+
+impl<'f, T: Scalar, F: Field<T>> View<'f, F, T> {
+    /// Create a view of `field` whose samples lie in the rectangle with the given origin and
+    /// size.
+    ///
+    /// Panics if the rectangle is not entirely inside the field.
+    pub(crate) fn crop(field: &'f F, origin: (usize, usize), size: (usize, usize)) -> Self {
+        let (w, h) = field.dimensions();
+        assert!(
+            origin.0 + size.0 <= w && origin.1 + size.1 <= h,
+            "the cropped region is outside the field"
+        );
+
+        View::new(field, size, origin, (1, 0), (0, 1))
+    }
+
+    /// Create a view of `field` made of one sample every `step` along both axes.
+    ///
+    /// Panics if `step` is zero.
+    pub(crate) fn step_by(field: &'f F, step: usize) -> Self {
+        assert!(step > 0, "the step cannot be zero");
+
+        let (w, h) = field.dimensions();
+        let dimensions = (w.div_ceil(step), h.div_ceil(step));
+        let step = step as isize;
+
+        View::new(field, dimensions, (0, 0), (step, 0), (0, step))
+    }
+
+    pub(crate) fn transpose(field: &'f F) -> Self {
+        let (w, h) = field.dimensions();
+        View::new(field, (h, w), (0, 0), (0, 1), (1, 0))
+    }
+
+    pub(crate) fn flip_x(field: &'f F) -> Self {
+        let (w, h) = field.dimensions();
+        View::new(field, (w, h), (w.saturating_sub(1), 0), (-1, 0), (0, 1))
+    }
+
+    pub(crate) fn flip_y(field: &'f F) -> Self {
+        let (w, h) = field.dimensions();
+        View::new(field, (w, h), (0, h.saturating_sub(1)), (1, 0), (0, -1))
+    }
+
+    fn new(
+        field: &'f F,
+        dimensions: (usize, usize),
+        origin: (usize, usize),
+        x_axis: (isize, isize),
+        y_axis: (isize, isize),
+    ) -> Self {
+        View {
+            field,
+            dimensions,
+            origin,
+            x_axis,
+            y_axis,
+            _value: PhantomData,
+        }
+    }
+
+    /// The transform that maps the coordinates of the view to the coordinates of the parent
+    /// field. Use `Transform::then` to map the contours found on a view of a view all the way
+    /// back to the original field.
+    pub fn transform(&self) -> Transform {
+        let f = |(x, y): (isize, isize)| (x as f64, y as f64);
+
+        Transform {
+            origin: (self.origin.0 as f64, self.origin.1 as f64),
+            x_axis: f(self.x_axis),
+            y_axis: f(self.y_axis),
+        }
+    }
+
+    /// The position of the sample of the parent field at the given position of the view.
+    fn parent(&self, x: usize, y: usize) -> (usize, usize) {
+        let (x, y) = (x as isize, y as isize);
+        (
+            self.origin
+                .0
+                .wrapping_add_signed(x * self.x_axis.0 + y * self.y_axis.0),
+            self.origin
+                .1
+                .wrapping_add_signed(x * self.x_axis.1 + y * self.y_axis.1),
+        )
+    }
+}
+
+impl<T: Scalar, F: Field<T>> Field<T> for View<'_, F, T> {
+    fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
+    fn z_at(&self, x: usize, y: usize) -> T {
+        let (x, y) = self.parent(x, y);
+        self.field.z_at(x, y)
+    }
+
+    fn is_valid(&self, x: usize, y: usize) -> bool {
+        let (x, y) = self.parent(x, y);
+        self.field.is_valid(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+    use crate::nesting::signed_area;
+    use crate::{march, march_transformed, Field};
+
+    fn grid() -> Grid<u8> {
+        #[rustfmt::skip]
+        let samples = vec![
+            0, 0, 0, 0, 0, 0,
+            0, 1, 1, 0, 0, 0,
+            0, 1, 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0,
+        ];
+
+        Grid::new(6, 5, samples)
+    }
+
+    #[test]
+    fn test_crop() {
+        let grid = grid();
+        let view = grid.crop(0, 0, 4, 4);
+
+        assert_eq!(view.dimensions(), (4, 4));
+        assert_eq!(
+            march_transformed(&view, 0.5, &view.transform()),
+            vec![march(&grid, 0.5)[0].clone()]
+        );
+
+        let view = grid.crop(3, 3, 3, 2);
+        assert_eq!(view.z_at(1, 1), 1);
+        assert_eq!(
+            march_transformed(&view, 0.5, &view.transform()),
+            vec![vec![(4.5, 4.0), (4.0, 3.5), (3.5, 4.0)]]
+        );
+    }
+
+    #[test]
+    fn test_step_by() {
+        let grid = grid();
+        let view = grid.step_by(2);
+
+        assert_eq!(view.dimensions(), (3, 3));
+        assert_eq!(view.z_at(2, 2), 1);
+        assert_eq!(view.transform().apply((2.0, 2.0)), (4.0, 4.0));
+    }
+
+    #[test]
+    fn test_transpose_and_flip() {
+        let grid = grid();
+        let original = march(&grid, 0.5);
+
+        let transposed = grid.transpose();
+        assert_eq!(transposed.dimensions(), (5, 6));
+        assert_eq!(transposed.z_at(4, 4), 1);
+
+        for view in [grid.transpose(), grid.flip_x(), grid.flip_y()] {
+            let contours = march_transformed(&view, 0.5, &view.transform());
+            assert_eq!(contours.len(), original.len());
+
+            // the views mirror the field, thus the contours are wound the other way around
+            let closed = contours.iter().find(|c| c[0] == c[c.len() - 1]).unwrap();
+            assert_eq!(signed_area(closed), -signed_area(&original[0]));
+        }
+
+        assert_eq!(grid.flip_x().z_at(1, 4), 1);
+        assert_eq!(grid.flip_y().z_at(4, 0), 1);
+    }
+
+    #[test]
+    fn test_nested_views() {
+        let grid = grid();
+        let inner = grid.crop(2, 2, 4, 3);
+        let view = inner.flip_y();
+
+        assert_eq!(view.z_at(2, 0), 1);
+
+        let transform = view.transform().then(&inner.transform());
+        assert_eq!(transform.apply((2.0, 0.0)), (4.0, 4.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_crop_outside() {
+        grid().crop(3, 3, 4, 2);
+    }
+}